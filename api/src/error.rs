@@ -0,0 +1,46 @@
+use super::prelude::*;
+
+use bson::oid::ObjectId;
+use std::fmt::{Formatter, Result as FmtResult};
+
+/// A stable, client-facing classification of an error.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    NotAuthenticated,
+    Forbidden,
+    NotFound,
+    InvalidInput,
+    Conflict,
+    RateLimited,
+    Internal,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        use ErrorCode::*;
+        match self {
+            NotAuthenticated => "NOT_AUTHENTICATED",
+            Forbidden => "FORBIDDEN",
+            NotFound => "NOT_FOUND",
+            InvalidInput => "INVALID_INPUT",
+            Conflict => "CONFLICT",
+            RateLimited => "RATE_LIMITED",
+            Internal => "INTERNAL",
+        }
+    }
+}
+
+impl Display for ErrorCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Logs an internal error, and returns a correlation ID that can be given to
+/// clients in place of the error's details.
+pub fn report_error(error: &Error) -> String {
+    let correlation_id = ObjectId::new().to_hex();
+    error!(target: "server", %correlation_id, "{:#}", error);
+    correlation_id
+}
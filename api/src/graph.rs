@@ -3,16 +3,32 @@ mod utils;
 
 mod build;
 mod date;
+mod identity;
+mod impersonation;
+mod invitation;
 mod membership;
 mod mutation;
 mod node;
 mod query;
+mod search;
+mod service;
+mod skill;
+mod team;
+mod term;
 mod user;
 
 pub use build::*;
 pub use date::*;
+pub use identity::*;
+pub use impersonation::*;
+pub use invitation::*;
 pub use membership::*;
 pub use mutation::*;
 pub use node::*;
 pub use query::*;
+pub use search::*;
+pub use service::*;
+pub use skill::*;
+pub use team::*;
+pub use term::*;
 pub use user::*;
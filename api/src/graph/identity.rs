@@ -0,0 +1,129 @@
+use super::prelude::*;
+
+use crate::identity::Identifier;
+
+entity_object! {
+    #[graphql(name = "IdentityLink")]
+    pub struct IdentityLinkObject(IdentityLink) {
+        provider: String,
+        subject: String,
+        email: String,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IdentityMutations;
+
+#[Object]
+impl IdentityMutations {
+    /// Links another identity to the viewer, so that they can also sign in
+    /// with it. `token` must be a token for that identity, from this server's
+    /// identity provider.
+    async fn link_identity(
+        &self,
+        ctx: &Context<'_>,
+        input: LinkIdentityInput,
+    ) -> FieldResult<LinkIdentityPayload> {
+        let LinkIdentityInput { token } = input;
+
+        with_identity(ctx)?;
+        let viewer = with_viewer(ctx).await?;
+        let identity = {
+            let identifier = ctx.data::<Arc<dyn Identifier>>()?;
+            identifier.identify(&token).await.map_err(|error| {
+                debug!(
+                    target: "lattice-api::graph",
+                    "invalid identity token: {:#}", error
+                );
+                field_error(ErrorCode::InvalidInput, "invalid token")
+            })?
+        };
+        let claims = identity
+            .claims()
+            .ensure(ErrorCode::InvalidInput, "invalid token")?;
+
+        let link = IdentityLink::find_by_subject(&claims.iss, &claims.sub)
+            .load(ctx.entity())
+            .await
+            .extend("failed to load identity link")?;
+        if let Some(link) = link {
+            if link.user.id != viewer.id {
+                let error = field_error(
+                    ErrorCode::Conflict,
+                    "identity is linked to another account",
+                );
+                return Err(error);
+            }
+            let payload = LinkIdentityPayload {
+                identity_link: link.into(),
+            };
+            return Ok(payload);
+        }
+        let link = link_identity(ctx, &viewer, claims).await?;
+
+        let payload = LinkIdentityPayload {
+            identity_link: link.into(),
+        };
+        Ok(payload)
+    }
+
+    async fn unlink_identity(
+        &self,
+        ctx: &Context<'_>,
+        input: UnlinkIdentityInput,
+    ) -> FieldResult<UnlinkIdentityPayload> {
+        let UnlinkIdentityInput { identity_link_id } = input;
+
+        let claims = with_identity(ctx)?;
+        let viewer = with_viewer(ctx).await?;
+        let mut link = {
+            let id = identity_link_id
+                .get::<IdentityLink>()
+                .ensure(ErrorCode::InvalidInput, "invalid identity link ID")?;
+            IdentityLink::find(&id)
+                .load(ctx.entity())
+                .await
+                .extend("failed to load identity link")?
+                .ensure(ErrorCode::NotFound, "identity link not found")?
+        };
+        if link.user.id != viewer.id {
+            let error = field_error(ErrorCode::Forbidden, "not authorized");
+            return Err(error);
+        }
+        if link.provider == claims.iss && link.subject == claims.sub {
+            let error = field_error(
+                ErrorCode::InvalidInput,
+                "can't unlink the identity you're signed in with",
+            );
+            return Err(error);
+        }
+        link.delete(ctx.entity())
+            .await
+            .extend("failed to delete identity link")?;
+
+        let payload = UnlinkIdentityPayload {
+            identity_link_id: link.global_id().into(),
+        };
+        Ok(payload)
+    }
+}
+
+#[derive(Debug, Clone, InputObject)]
+struct LinkIdentityInput {
+    token: String,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+struct LinkIdentityPayload {
+    identity_link: IdentityLinkObject,
+}
+
+#[derive(Debug, Clone, InputObject)]
+struct UnlinkIdentityInput {
+    identity_link_id: NodeId,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+struct UnlinkIdentityPayload {
+    identity_link_id: NodeId,
+}
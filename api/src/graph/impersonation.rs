@@ -0,0 +1,104 @@
+use super::prelude::*;
+
+use std::cmp::Reverse;
+
+entity_object! {
+    #[graphql(name = "Impersonation", complex)]
+    pub struct ImpersonationObject(Impersonation) {
+        request_count: u32,
+    }
+}
+
+#[ComplexObject]
+impl ImpersonationObject {
+    async fn admin(&self, ctx: &Context<'_>) -> FieldResult<UserObject> {
+        let user = self
+            .entity
+            .admin()
+            .load(ctx.entity())
+            .await
+            .extend("failed to load user")?
+            .ensure(ErrorCode::NotFound, "user not found")?;
+        let user = UserObject::from(user);
+        Ok(user)
+    }
+
+    async fn target(&self, ctx: &Context<'_>) -> FieldResult<UserObject> {
+        let user = self
+            .entity
+            .target()
+            .load(ctx.entity())
+            .await
+            .extend("failed to load user")?
+            .ensure(ErrorCode::NotFound, "user not found")?;
+        let user = UserObject::from(user);
+        Ok(user)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ImpersonationQueries;
+
+#[Object]
+impl ImpersonationQueries {
+    /// The current impersonation session, if an admin is impersonating the
+    /// viewer.
+    async fn impersonation(
+        &self,
+        ctx: &Context<'_>,
+    ) -> Option<ImpersonationObject> {
+        let identity: &Identity = ctx.data_opt()?;
+        let impersonation = identity.impersonation()?;
+        let session = ImpersonationObject::from(impersonation.session.clone());
+        Some(session)
+    }
+
+    /// Recorded impersonation sessions, most recent first. Only available to
+    /// admins.
+    async fn impersonations(
+        &self,
+        ctx: &Context<'_>,
+        admin_id: Option<NodeId>,
+        target_id: Option<NodeId>,
+    ) -> FieldResult<Vec<ImpersonationObject>> {
+        with_admin(ctx).await?;
+
+        let admin_ref: Option<ObjectRef> = match admin_id {
+            Some(admin_id) => {
+                let admin_id = admin_id
+                    .get::<User>()
+                    .ensure(ErrorCode::InvalidInput, "invalid admin ID")?;
+                Some(admin_id.into())
+            }
+            None => None,
+        };
+        let target_ref: Option<ObjectRef> = match target_id {
+            Some(target_id) => {
+                let target_id = target_id
+                    .get::<User>()
+                    .ensure(ErrorCode::InvalidInput, "invalid target ID")?;
+                Some(target_id.into())
+            }
+            None => None,
+        };
+        let conditions = ImpersonationConditions::builder()
+            .admin(admin_ref)
+            .target(target_ref)
+            .build();
+        let impersonations = Impersonation::filter(conditions)
+            .find(ctx.entity())
+            .await
+            .extend("failed to find impersonations")?;
+        let mut impersonations: Vec<_> = impersonations
+            .try_collect()
+            .await
+            .extend("failed to load impersonations")?;
+        impersonations.sort_by_key(|session| Reverse(session.updated_at));
+
+        let impersonations: Vec<_> = impersonations
+            .into_iter()
+            .map(ImpersonationObject::from)
+            .collect();
+        Ok(impersonations)
+    }
+}
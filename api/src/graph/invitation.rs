@@ -0,0 +1,329 @@
+use super::prelude::*;
+
+use std::cmp::Reverse;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+#[graphql(name = "InvitationStatus", remote = "InvitationStatus")]
+pub enum InvitationStatusEnum {
+    Pending,
+    Accepted,
+    Revoked,
+    Expired,
+}
+
+entity_object! {
+    #[graphql(name = "Invitation", complex)]
+    pub struct InvitationObject(Invitation) {
+        email: String,
+        expires_at: DateTimeScalar,
+    }
+}
+
+#[ComplexObject]
+impl InvitationObject {
+    async fn status(&self) -> InvitationStatusEnum {
+        self.entity.status().into()
+    }
+
+    async fn revoked_at(&self) -> Option<DateTimeScalar> {
+        self.entity.revoked_at.map(Into::into)
+    }
+
+    async fn accepted_at(&self) -> Option<DateTimeScalar> {
+        self.entity.accepted_at.map(Into::into)
+    }
+
+    async fn role(&self, ctx: &Context<'_>) -> FieldResult<MemberRoleObject> {
+        let role = self
+            .entity
+            .role()
+            .load(ctx.entity())
+            .await
+            .extend("failed to load member role")?
+            .ensure(ErrorCode::NotFound, "member role not found")?;
+        let role = MemberRoleObject::from(role);
+        Ok(role)
+    }
+
+    async fn term(&self, ctx: &Context<'_>) -> FieldResult<TermObject> {
+        let term = self
+            .entity
+            .term()
+            .load(ctx.entity())
+            .await
+            .extend("failed to load term")?
+            .ensure(ErrorCode::NotFound, "term not found")?;
+        let term = TermObject::from(term);
+        Ok(term)
+    }
+
+    async fn inviter(&self, ctx: &Context<'_>) -> FieldResult<UserObject> {
+        let user = self
+            .entity
+            .inviter()
+            .load(ctx.entity())
+            .await
+            .extend("failed to load user")?
+            .ensure(ErrorCode::NotFound, "user not found")?;
+        let user = UserObject::from(user);
+        Ok(user)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InvitationQueries;
+
+#[Object]
+impl InvitationQueries {
+    async fn invitations(
+        &self,
+        ctx: &Context<'_>,
+        email: Option<String>,
+        status: Option<InvitationStatusEnum>,
+    ) -> FieldResult<Vec<InvitationObject>> {
+        let inviter = with_inviter(ctx).await?;
+
+        let conditions = InvitationConditions::builder().email(email).build();
+        let invitations = Invitation::filter(conditions)
+            .find(ctx.entity())
+            .await
+            .extend("failed to find invitations")?;
+        let mut invitations: Vec<_> = invitations
+            .try_collect()
+            .await
+            .extend("failed to load invitations")?;
+        if let Some(status) = status {
+            let status = InvitationStatus::from(status);
+            invitations.retain(|invitation| invitation.status() == status);
+        }
+        if inviter.roles.is_some() {
+            let mut grantable = Vec::with_capacity(invitations.len());
+            for invitation in invitations {
+                if inviter.can_grant(ctx, &invitation.role).await? {
+                    grantable.push(invitation);
+                }
+            }
+            invitations = grantable;
+        }
+        invitations.sort_by_key(|invitation| Reverse(invitation.created_at));
+        let invitations: Vec<_> = invitations
+            .into_iter()
+            .map(InvitationObject::from)
+            .collect();
+        Ok(invitations)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InvitationMutations;
+
+#[Object]
+impl InvitationMutations {
+    async fn create_invitation(
+        &self,
+        ctx: &Context<'_>,
+        input: CreateInvitationInput,
+    ) -> FieldResult<CreateInvitationPayload> {
+        let CreateInvitationInput {
+            email,
+            role_id,
+            term_id,
+            expires_at,
+        } = input;
+
+        let inviter = with_inviter(ctx).await?;
+        let role_ref: ObjectRef = role_id
+            .get::<MemberRole>()
+            .ensure(ErrorCode::InvalidInput, "invalid member role ID")?
+            .into();
+        if !inviter.can_grant(ctx, &role_ref).await? {
+            let error = field_error(
+                ErrorCode::Forbidden,
+                "not authorized to invite members to this role",
+            );
+            return Err(error);
+        }
+        let term_ref: ObjectRef = term_id
+            .get::<Term>()
+            .ensure(ErrorCode::InvalidInput, "invalid term ID")?
+            .into();
+        let expires_at: DateTime = match expires_at {
+            Some(expires_at) => expires_at.into(),
+            None => Utc::now() + Duration::days(Invitation::DEFAULT_TTL_DAYS),
+        };
+        if expires_at <= Utc::now() {
+            let error = field_error(
+                ErrorCode::InvalidInput,
+                "invitation must expire in the future",
+            );
+            return Err(error);
+        }
+
+        let token = Invitation::generate_token();
+        let mut invitation = Invitation::builder()
+            .email(email)
+            .role(role_ref)
+            .term(term_ref)
+            .inviter(inviter.user.object_ref())
+            .expires_at(expires_at)
+            .token_hash(Invitation::hash_token(&token))
+            .build();
+        invitation
+            .save(ctx.entity())
+            .await
+            .extend("failed to save invitation")?;
+
+        let invitation = InvitationObject::from(invitation);
+        let payload = CreateInvitationPayload { invitation, token };
+        Ok(payload)
+    }
+
+    async fn revoke_invitation(
+        &self,
+        ctx: &Context<'_>,
+        input: RevokeInvitationInput,
+    ) -> FieldResult<RevokeInvitationPayload> {
+        let RevokeInvitationInput { invitation_id } = input;
+
+        let inviter = with_inviter(ctx).await?;
+        let mut invitation = {
+            let id = invitation_id
+                .get::<Invitation>()
+                .ensure(ErrorCode::InvalidInput, "invalid invitation ID")?;
+            Invitation::find(&id)
+                .load(ctx.entity())
+                .await
+                .extend("failed to load invitation")?
+                .ensure(ErrorCode::NotFound, "invitation not found")?
+        };
+        if !inviter.can_grant(ctx, &invitation.role).await? {
+            let error = field_error(ErrorCode::Forbidden, "not authorized");
+            return Err(error);
+        }
+        invitation
+            .revoke(ctx.entity())
+            .await
+            .extend("failed to revoke invitation")?;
+
+        let invitation = InvitationObject::from(invitation);
+        let payload = RevokeInvitationPayload { invitation };
+        Ok(payload)
+    }
+}
+
+/// A viewer who may manage invitations.
+struct Inviter {
+    user: User,
+
+    /// The roles that the viewer may invite people to (along with the roles
+    /// that report to them), or `None` if they're an admin, who may invite
+    /// people to any role.
+    roles: Option<Vec<ObjectId>>,
+}
+
+impl Inviter {
+    /// Returns whether the inviter may invite people to the role, which is
+    /// only the case for roles no higher than their own.
+    async fn can_grant(
+        &self,
+        ctx: &Context<'_>,
+        role_ref: &ObjectRef,
+    ) -> FieldResult<bool> {
+        let role_ids = match &self.roles {
+            Some(role_ids) => role_ids,
+            None => return Ok(true),
+        };
+        let role = MemberRole::find(&role_ref.id)
+            .load(ctx.entity())
+            .await
+            .extend("failed to load member role")?
+            .ensure(ErrorCode::NotFound, "member role not found")?;
+        let can_grant = role
+            .is_within(ctx.entity(), role_ids)
+            .await
+            .extend("failed to load member roles")?;
+        Ok(can_grant)
+    }
+}
+
+/// Returns the viewer if they're an admin or an exec, since only they may
+/// manage invitations.
+///
+/// Execs are members who currently hold a role that other roles report to.
+async fn with_inviter(ctx: &Context<'_>) -> FieldResult<Inviter> {
+    let viewer = with_member_account(ctx).await?;
+    if viewer.is_admin {
+        let inviter = Inviter {
+            user: viewer,
+            roles: None,
+        };
+        return Ok(inviter);
+    }
+
+    let today = Utc::today().naive_utc();
+    let conditions = MembershipConditions::builder()
+        .user(viewer.object_ref())
+        .active_at(today)
+        .build();
+    let memberships: Vec<Membership> = Membership::filter(conditions)
+        .find(ctx.entity())
+        .await
+        .extend("failed to find memberships")?
+        .try_collect()
+        .await
+        .extend("failed to load memberships")?;
+    let mut role_ids = Vec::new();
+    for membership in memberships {
+        let role = membership
+            .role()
+            .load(ctx.entity())
+            .await
+            .extend("failed to load member role")?
+            .ensure(ErrorCode::NotFound, "member role not found")?;
+        let has_reports = role
+            .direct_reports()
+            .count(ctx.entity())
+            .await
+            .extend("failed to count member roles")?
+            .is_positive();
+        if has_reports && !role_ids.contains(&role.id) {
+            role_ids.push(role.id);
+        }
+    }
+    if role_ids.is_empty() {
+        let error = field_error(ErrorCode::Forbidden, "not authorized");
+        return Err(error);
+    }
+
+    let inviter = Inviter {
+        user: viewer,
+        roles: Some(role_ids),
+    };
+    Ok(inviter)
+}
+
+#[derive(Debug, Clone, InputObject)]
+struct CreateInvitationInput {
+    email: String,
+    role_id: NodeId,
+    term_id: NodeId,
+    expires_at: Option<DateTimeScalar>,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+struct CreateInvitationPayload {
+    invitation: InvitationObject,
+
+    /// The token to send to the invitee. It can't be retrieved again.
+    token: String,
+}
+
+#[derive(Debug, Clone, InputObject)]
+struct RevokeInvitationInput {
+    invitation_id: NodeId,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+struct RevokeInvitationPayload {
+    invitation: InvitationObject,
+}
@@ -1,84 +1,120 @@
 use super::prelude::*;
 
-#[derive(Debug, Clone, From, Deref)]
-pub struct MembershipObject(Membership);
-
-#[Object(name = "Membership")]
-impl MembershipObject {
-    async fn id(&self) -> NodeId {
-        self.global_id().into()
-    }
-
-    async fn created_at(&self) -> DateTimeScalar {
-        let date_time = self.created_at.clone();
-        date_time.into()
-    }
-
-    async fn updated_at(&self) -> DateTimeScalar {
-        let date_time = self.updated_at.clone();
-        date_time.into()
-    }
-
-    async fn start(&self) -> DateScalar {
-        let date = self.start.clone();
-        date.into()
-    }
-
-    async fn end(&self) -> DateScalar {
-        let date = self.end.clone();
-        date.into()
+entity_object! {
+    #[graphql(name = "Membership", complex)]
+    pub struct MembershipObject(Membership) {
+        start: DateScalar,
+        end: DateScalar,
     }
+}
 
+#[ComplexObject]
+impl MembershipObject {
     async fn user(&self, ctx: &Context<'_>) -> FieldResult<UserObject> {
         let user = self
-            .0
+            .entity
             .user()
             .load(ctx.entity())
             .await
             .extend("failed to load user")?
-            .ensure("user not found")?;
+            .ensure(ErrorCode::NotFound, "user not found")?;
         let user = UserObject::from(user);
         Ok(user)
     }
 
     async fn role(&self, ctx: &Context<'_>) -> FieldResult<MemberRoleObject> {
         let role = self
-            .0
+            .entity
             .role()
             .load(ctx.entity())
             .await
             .extend("failed to load member role")?
-            .ensure("member role not found")?;
+            .ensure(ErrorCode::NotFound, "member role not found")?;
         let role = MemberRoleObject::from(role);
         Ok(role)
     }
-}
 
-#[derive(Debug, Clone, From, Deref)]
-pub struct MemberRoleObject(MemberRole);
+    async fn term(&self, ctx: &Context<'_>) -> FieldResult<Option<TermObject>> {
+        let term = match self.entity.term() {
+            Some(query) => query
+                .load(ctx.entity())
+                .await
+                .extend("failed to load term")?,
+            None => None,
+        };
+        let term = term.map(TermObject::from);
+        Ok(term)
+    }
 
-#[Object(name = "MemberRole")]
-impl MemberRoleObject {
-    async fn id(&self) -> NodeId {
-        self.global_id().into()
+    async fn terms(&self, ctx: &Context<'_>) -> FieldResult<Vec<TermObject>> {
+        let terms = self
+            .entity
+            .terms()
+            .find(ctx.entity())
+            .await
+            .extend("failed to find terms")?;
+        let terms: Vec<_> =
+            terms.try_collect().await.extend("failed to load terms")?;
+        let terms: Vec<_> = terms.into_iter().map(TermObject::from).collect();
+        Ok(terms)
     }
+}
 
-    async fn created_at(&self) -> DateTimeScalar {
-        let date_time = self.created_at.clone();
-        date_time.into()
+entity_object! {
+    #[graphql(name = "MemberRole", complex)]
+    pub struct MemberRoleObject(MemberRole) {
+        name: String,
+        description: String,
+        max_duration_days: Option<u32>,
     }
+}
 
-    async fn updated_at(&self) -> DateTimeScalar {
-        let date_time = self.updated_at.clone();
-        date_time.into()
+#[ComplexObject]
+impl MemberRoleObject {
+    async fn team(&self, ctx: &Context<'_>) -> FieldResult<Option<TeamObject>> {
+        let team = match self.entity.team() {
+            Some(query) => query
+                .load(ctx.entity())
+                .await
+                .extend("failed to load team")?,
+            None => None,
+        };
+        let team = team.map(TeamObject::from);
+        Ok(team)
     }
 
-    async fn name(&self) -> &String {
-        &self.name
+    async fn reports_to(
+        &self,
+        ctx: &Context<'_>,
+    ) -> FieldResult<Option<MemberRoleObject>> {
+        let role = match self.entity.reports_to() {
+            Some(query) => query
+                .load(ctx.entity())
+                .await
+                .extend("failed to load member role")?,
+            None => None,
+        };
+        let role = role.map(MemberRoleObject::from);
+        Ok(role)
     }
 
-    async fn description(&self) -> &String {
-        &self.description
+    async fn direct_reports(
+        &self,
+        ctx: &Context<'_>,
+    ) -> FieldResult<Vec<MemberRoleObject>> {
+        let roles = self
+            .entity
+            .direct_reports()
+            .find(ctx.entity())
+            .await
+            .extend("failed to find member roles")?;
+        let roles: Vec<_> = roles
+            .try_collect()
+            .await
+            .extend("failed to load member roles")?;
+        let roles: Vec<_> =
+            roles.into_iter().map(MemberRoleObject::from).collect();
+        Ok(roles)
     }
 }
 
@@ -87,6 +123,8 @@ pub struct MembershipQueries;
 
 #[Object]
 impl MembershipQueries {
+    /// All member roles. Available to every account; who holds them is
+    /// only available to members.
     async fn member_roles(
         &self,
         ctx: &Context<'_>,
@@ -103,6 +141,71 @@ impl MembershipQueries {
             roles.into_iter().map(MemberRoleObject::from).collect();
         Ok(roles)
     }
+
+    async fn roster(
+        &self,
+        ctx: &Context<'_>,
+        date: Option<DateScalar>,
+        role_ids: Option<Vec<NodeId>>,
+    ) -> FieldResult<Vec<UserObject>> {
+        authorize_member(ctx, Scope::MembershipsRead).await?;
+
+        let date: Date = match date {
+            Some(date) => date.into(),
+            None => Utc::today().naive_utc(),
+        };
+        let role_refs: Option<Vec<ObjectRef>> = match role_ids {
+            Some(role_ids) => {
+                let refs = role_ids
+                    .into_iter()
+                    .map(|role_id| {
+                        let id = role_id.get::<MemberRole>().ensure(
+                            ErrorCode::InvalidInput,
+                            "invalid member role ID",
+                        )?;
+                        Ok(id.into())
+                    })
+                    .collect::<FieldResult<Vec<_>>>()?;
+                Some(refs)
+            }
+            None => None,
+        };
+
+        let conditions = MembershipConditions::builder()
+            .active_at(date)
+            .roles(role_refs)
+            .build();
+        let memberships = Membership::filter(conditions)
+            .find(ctx.entity())
+            .await
+            .extend("failed to find memberships")?;
+        let memberships: Vec<_> = memberships
+            .try_collect()
+            .await
+            .extend("failed to load memberships")?;
+
+        let mut user_ids: Vec<ObjectId> = memberships
+            .into_iter()
+            .map(|membership| membership.user.id)
+            .collect();
+        user_ids.sort();
+        user_ids.dedup();
+
+        let conditions = UserConditions::builder().ids(user_ids).build();
+        let users = User::filter(conditions)
+            .find(ctx.entity())
+            .await
+            .extend("failed to find users")?;
+        let mut users: Vec<_> =
+            users.try_collect().await.extend("failed to load users")?;
+        users.sort_by(|a, b| {
+            let a = (&a.first_name, &a.last_name);
+            let b = (&b.first_name, &b.last_name);
+            a.cmp(&b)
+        });
+        let users: Vec<_> = users.into_iter().map(UserObject::from).collect();
+        Ok(users)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -115,11 +218,24 @@ impl MembershipMutations {
         ctx: &Context<'_>,
         input: CreateMemberRoleInput,
     ) -> FieldResult<CreateMemberRolePayload> {
-        let CreateMemberRoleInput { name, description } = input;
+        authorize_admin(ctx, Scope::MembershipsWrite).await?;
+
+        let CreateMemberRoleInput {
+            name,
+            description,
+            max_duration_days,
+            team_id,
+            reports_to_id,
+        } = input;
+        let team_ref = team_ref(team_id)?;
+        let reports_to_ref = member_role_ref(reports_to_id)?;
 
         let mut role = MemberRole::builder()
             .name(name)
             .description(description)
+            .max_duration_days(max_duration_days)
+            .team(team_ref)
+            .reports_to(reports_to_ref)
             .build();
         role.save(ctx.entity())
             .await
@@ -135,26 +251,36 @@ impl MembershipMutations {
         ctx: &Context<'_>,
         input: UpdateMemberRoleInput,
     ) -> FieldResult<UpdateMemberRolePayload> {
+        authorize_admin(ctx, Scope::MembershipsWrite).await?;
+
         let UpdateMemberRoleInput {
             role_id,
             name,
             description,
+            max_duration_days,
+            team_id,
+            reports_to_id,
         } = input;
 
         let role = {
             let id = role_id
                 .get::<MemberRole>()
-                .ensure("invalid member role ID")?;
+                .ensure(ErrorCode::InvalidInput, "invalid member role ID")?;
             MemberRole::find(&id)
                 .load(ctx.entity())
                 .await
                 .extend("failed to load member role")?
-                .ensure("member role not found")?
+                .ensure(ErrorCode::NotFound, "member role not found")?
         };
+        let team_ref = team_ref(team_id)?;
+        let reports_to_ref = member_role_ref(reports_to_id)?;
 
         let mut role = MemberRole {
             name,
             description,
+            max_duration_days,
+            team: team_ref,
+            reports_to: reports_to_ref,
             ..role
         };
         role.save(ctx.entity())
@@ -171,16 +297,18 @@ impl MembershipMutations {
         ctx: &Context<'_>,
         input: DeleteMemberRoleInput,
     ) -> FieldResult<DeleteMemberRolePayload> {
+        authorize_admin(ctx, Scope::MembershipsWrite).await?;
+
         let DeleteMemberRoleInput { role_id } = input;
 
         let role_id = role_id
             .get::<MemberRole>()
-            .ensure("invalid member role ID")?;
+            .ensure(ErrorCode::InvalidInput, "invalid member role ID")?;
         let mut role = MemberRole::find(&role_id)
             .load(ctx.entity())
             .await
             .extend("failed to load member role")?
-            .ensure("member role not found")?;
+            .ensure(ErrorCode::NotFound, "member role not found")?;
         role.delete(ctx.entity())
             .await
             .extend("failed to delete member role")?;
@@ -199,30 +327,30 @@ impl MembershipMutations {
         let CreateMembershipInput {
             user_id,
             role_id,
+            term_id,
             start,
             end,
         } = input;
 
         let user_ref: ObjectRef = {
-            let id = user_id.get::<User>().ensure("invalid user ID")?;
+            let id = user_id
+                .get::<User>()
+                .ensure(ErrorCode::InvalidInput, "invalid user ID")?;
             id.into()
         };
         let role_ref: ObjectRef = {
             let id = role_id
                 .get::<MemberRole>()
-                .ensure("invalid member role ID")?;
+                .ensure(ErrorCode::InvalidInput, "invalid member role ID")?;
             id.into()
         };
-
-        let viewer = with_viewer(ctx).await?;
-        if viewer.object_ref() != user_ref {
-            let error = format_err!("not authorized");
-            return Err(error.into());
-        }
+        let term_ref = term_ref(term_id)?;
+        authorize_membership_write(ctx, &user_ref).await?;
 
         let mut membership = Membership::builder()
             .user(user_ref)
             .role(role_ref)
+            .term(term_ref)
             .start(start)
             .end(end)
             .build();
@@ -244,6 +372,7 @@ impl MembershipMutations {
         let UpdateMembershipInput {
             membership_id,
             role_id,
+            term_id,
             start,
             end,
         } = input;
@@ -251,28 +380,27 @@ impl MembershipMutations {
         let membership = {
             let id = membership_id
                 .get::<Membership>()
-                .ensure("invalid membership ID")?;
+                .ensure(ErrorCode::InvalidInput, "invalid membership ID")?;
             Membership::find(&id)
                 .load(ctx.entity())
                 .await
                 .extend("failed to load membership")?
-                .ensure("membership not found")?
+                .ensure(ErrorCode::NotFound, "membership not found")?
         };
 
         let user_ref = membership.user.clone();
         let role_ref: ObjectRef = {
-            let id = role_id.get::<MemberRole>().ensure("invalid role ID")?;
+            let id = role_id
+                .get::<MemberRole>()
+                .ensure(ErrorCode::InvalidInput, "invalid role ID")?;
             id.into()
         };
-
-        let viewer = with_viewer(ctx).await?;
-        if viewer.object_ref() != user_ref {
-            let error = format_err!("not authorized");
-            return Err(error.into());
-        }
+        let term_ref = term_ref(term_id)?;
+        authorize_membership_write(ctx, &user_ref).await?;
 
         let mut membership = Membership {
             role: role_ref,
+            term: term_ref,
             start: start.into(),
             end: end.into(),
             ..membership
@@ -297,13 +425,15 @@ impl MembershipMutations {
         let mut membership = {
             let id = membership_id
                 .get::<Membership>()
-                .ensure("invalid membership ID")?;
+                .ensure(ErrorCode::InvalidInput, "invalid membership ID")?;
             Membership::find(&id)
                 .load(ctx.entity())
                 .await
                 .extend("failed to load membership")?
-                .ensure("membership not found")?
+                .ensure(ErrorCode::NotFound, "membership not found")?
         };
+        authorize_membership_write(ctx, &membership.user).await?;
+
         membership
             .delete(ctx.entity())
             .await
@@ -316,10 +446,53 @@ impl MembershipMutations {
     }
 }
 
+/// Checks that the requester may modify memberships of the given user: that
+/// user themself, an admin, or a service account with
+/// `Scope::MembershipsWrite`.
+async fn authorize_membership_write(
+    ctx: &Context<'_>,
+    user_ref: &ObjectRef,
+) -> FieldResult<()> {
+    if with_service_scope(ctx, Scope::MembershipsWrite)? {
+        return Ok(());
+    }
+    let viewer = with_viewer(ctx).await?;
+    if &viewer.object_ref() != user_ref && !viewer.is_admin {
+        let error = field_error(ErrorCode::Forbidden, "not authorized");
+        return Err(error);
+    }
+    Ok(())
+}
+
+fn term_ref(term_id: Option<NodeId>) -> FieldResult<Option<ObjectRef>> {
+    let term_id = match term_id {
+        Some(term_id) => term_id,
+        None => return Ok(None),
+    };
+    let id = term_id
+        .get::<Term>()
+        .ensure(ErrorCode::InvalidInput, "invalid term ID")?;
+    Ok(Some(id.into()))
+}
+
+fn member_role_ref(role_id: Option<NodeId>) -> FieldResult<Option<ObjectRef>> {
+    let role_id = match role_id {
+        Some(role_id) => role_id,
+        None => return Ok(None),
+    };
+    let id = role_id
+        .get::<MemberRole>()
+        .ensure(ErrorCode::InvalidInput, "invalid member role ID")?;
+    Ok(Some(id.into()))
+}
+
 #[derive(Debug, Clone, InputObject)]
 struct CreateMemberRoleInput {
     name: String,
     description: String,
+    max_duration_days: Option<u32>,
+    team_id: Option<NodeId>,
+    reports_to_id: Option<NodeId>,
 }
 
 #[derive(Debug, Clone, SimpleObject)]
@@ -332,6 +505,9 @@ struct UpdateMemberRoleInput {
     role_id: NodeId,
     name: String,
     description: String,
+    max_duration_days: Option<u32>,
+    team_id: Option<NodeId>,
+    reports_to_id: Option<NodeId>,
 }
 
 #[derive(Debug, Clone, SimpleObject)]
@@ -353,6 +529,7 @@ struct DeleteMemberRolePayload {
 struct CreateMembershipInput {
     user_id: NodeId,
     role_id: NodeId,
+    term_id: Option<NodeId>,
     start: DateScalar,
     end: DateScalar,
 }
@@ -366,6 +543,7 @@ struct CreateMembershipPayload {
 struct UpdateMembershipInput {
     membership_id: NodeId,
     role_id: NodeId,
+    term_id: Option<NodeId>,
     start: DateScalar,
     end: DateScalar,
 }
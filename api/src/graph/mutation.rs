@@ -1,10 +1,28 @@
 use super::prelude::*;
 
 #[derive(Debug, Clone, MergedObject)]
-pub struct Mutation(UserMutations, MembershipMutations);
+pub struct Mutation(
+    UserMutations,
+    IdentityMutations,
+    MembershipMutations,
+    InvitationMutations,
+    SkillMutations,
+    TeamMutations,
+    TermMutations,
+    ServiceMutations,
+);
 
 impl Mutation {
     pub fn new() -> Self {
-        Self(UserMutations, MembershipMutations)
+        Self(
+            UserMutations,
+            IdentityMutations,
+            MembershipMutations,
+            InvitationMutations,
+            SkillMutations,
+            TeamMutations,
+            TermMutations,
+            ServiceMutations,
+        )
     }
 }
@@ -1,14 +1,19 @@
 pub use super::utils::*;
 pub use super::*;
 
+pub use crate::error::{report_error, ErrorCode};
 pub use crate::identity::Claims as IdentityClaims;
 pub use crate::identity::Identity;
+pub use crate::photos::*;
+pub use crate::policy::EmailPolicy;
 pub use crate::prelude::*;
 
 pub use lattice::entities::Context as EntityContext;
 pub use lattice::entities::*;
+pub use lattice::Error as EntityError;
 
 pub use graphql::scalar;
-pub use graphql::{Context, FieldError, FieldResult};
+pub use graphql::{ComplexObject, InputObject, MergedObject};
+pub use graphql::{Context, ErrorExtensions, FieldError, FieldResult};
 pub use graphql::{Enum, Interface, Scalar};
-pub use graphql::{InputObject, MergedObject, Object, SimpleObject};
+pub use graphql::{Object, SimpleObject, Union, Upload};
@@ -1,10 +1,32 @@
 use super::prelude::*;
 
 #[derive(Debug, Clone, MergedObject)]
-pub struct Query(BuildQueries, UserQueries, MembershipQueries);
+pub struct Query(
+    BuildQueries,
+    UserQueries,
+    ImpersonationQueries,
+    MembershipQueries,
+    InvitationQueries,
+    SkillQueries,
+    TeamQueries,
+    TermQueries,
+    ServiceQueries,
+    SearchQueries,
+);
 
 impl Query {
     pub fn new() -> Self {
-        Self(BuildQueries, UserQueries, MembershipQueries)
+        Self(
+            BuildQueries,
+            UserQueries,
+            ImpersonationQueries,
+            MembershipQueries,
+            InvitationQueries,
+            SkillQueries,
+            TeamQueries,
+            TermQueries,
+            ServiceQueries,
+            SearchQueries,
+        )
     }
 }
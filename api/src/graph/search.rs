@@ -0,0 +1,220 @@
+use super::prelude::*;
+
+use base64::decode as decode_base64;
+use base64::encode as encode_base64;
+
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name = "SearchHighlight")]
+pub struct SearchHighlightObject {
+    pub field: String,
+    pub value: String,
+    pub ranges: Vec<SearchRangeObject>,
+}
+
+impl From<SearchHighlight> for SearchHighlightObject {
+    fn from(highlight: SearchHighlight) -> Self {
+        let SearchHighlight {
+            field,
+            value,
+            ranges,
+        } = highlight;
+        let ranges = ranges
+            .into_iter()
+            .map(|(start, end)| SearchRangeObject {
+                start: start as i32,
+                end: end as i32,
+            })
+            .collect();
+        Self {
+            field: camel_case(&field),
+            value,
+            ranges,
+        }
+    }
+}
+
+/// A half-open range of characters within a highlighted value.
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name = "SearchRange")]
+pub struct SearchRangeObject {
+    pub start: i32,
+    pub end: i32,
+}
+
+const DEFAULT_SEARCH_LIMIT: i32 = 10;
+const MAX_SEARCH_LIMIT: i32 = 50;
+
+/// Validates the number of search results requested by a client.
+pub fn search_limit(first: Option<i32>) -> FieldResult<u32> {
+    let first = first.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    if !(1..=MAX_SEARCH_LIMIT).contains(&first) {
+        let message =
+            format!("first must be between 1 and {}", MAX_SEARCH_LIMIT);
+        return Err(field_error(ErrorCode::InvalidInput, message));
+    }
+    Ok(first as u32)
+}
+
+fn camel_case(name: &str) -> String {
+    let mut output = String::with_capacity(name.len());
+    let mut upper = false;
+    for c in name.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            output.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            output.push(c);
+        }
+    }
+    output
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+pub enum SearchType {
+    User,
+    MemberRole,
+    Team,
+    Skill,
+}
+
+// Union members can't be boxed.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, Union)]
+pub enum SearchNode {
+    User(UserObject),
+    MemberRole(MemberRoleObject),
+    Team(TeamObject),
+    Skill(SkillObject),
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name = "SearchHit")]
+pub struct SearchHitObject {
+    pub node: SearchNode,
+    pub score: i32,
+    pub highlights: Vec<SearchHighlightObject>,
+    pub cursor: String,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub struct SearchConnection {
+    pub hits: Vec<SearchHitObject>,
+    pub end_cursor: Option<String>,
+    pub has_next_page: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchQueries;
+
+#[Object]
+impl SearchQueries {
+    async fn search(
+        &self,
+        ctx: &Context<'_>,
+        query: String,
+        types: Option<Vec<SearchType>>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> FieldResult<SearchConnection> {
+        authorize_member(ctx, Scope::UsersRead).await?;
+
+        let limit = search_limit(first)?;
+        let offset = match after {
+            Some(cursor) => decode_cursor(&cursor)? + 1,
+            None => 0,
+        };
+        let includes = |search_type: SearchType| match &types {
+            Some(types) => types.contains(&search_type),
+            None => true,
+        };
+
+        let mut search = GlobalSearchQuery::new(query);
+        if includes(SearchType::User) {
+            search = search.include::<User>();
+        }
+        if includes(SearchType::MemberRole) {
+            search = search.include::<MemberRole>();
+        }
+        if includes(SearchType::Team) {
+            search = search.include::<Team>();
+        }
+        if includes(SearchType::Skill) {
+            search = search.include::<Skill>();
+        }
+
+        // Request an extra hit to tell whether there is a next page.
+        let mut hits = search
+            .skip(offset)
+            .take(limit + 1)
+            .find(ctx.entity())
+            .await
+            .extend("failed to search")?;
+        let has_next_page = hits.len() > limit as usize;
+        hits.truncate(limit as usize);
+
+        let hits = hits
+            .into_iter()
+            .zip(offset..)
+            .map(|(hit, offset)| SearchHitObject::new(hit, offset))
+            .collect::<Result<Vec<_>, EntityError>>()
+            .extend("failed to load search hits")?;
+        let end_cursor = hits.last().map(|hit| hit.cursor.clone());
+        let connection = SearchConnection {
+            hits,
+            end_cursor,
+            has_next_page,
+        };
+        Ok(connection)
+    }
+}
+
+impl SearchHitObject {
+    fn new(hit: SearchHit, offset: u32) -> Result<Self, EntityError> {
+        let score = hit.score;
+        let highlights = hit
+            .highlights
+            .clone()
+            .into_iter()
+            .map(SearchHighlightObject::from)
+            .collect();
+        let object_type = hit.object_type.clone();
+        let node = if object_type == User::OBJECT_TYPE {
+            let user = hit.into_entity::<User>()?;
+            SearchNode::User(user.into())
+        } else if object_type == MemberRole::OBJECT_TYPE {
+            let role = hit.into_entity::<MemberRole>()?;
+            SearchNode::MemberRole(role.into())
+        } else if object_type == Team::OBJECT_TYPE {
+            let team = hit.into_entity::<Team>()?;
+            SearchNode::Team(team.into())
+        } else if object_type == Skill::OBJECT_TYPE {
+            let skill = hit.into_entity::<Skill>()?;
+            SearchNode::Skill(skill.into())
+        } else {
+            let message =
+                format!("unsupported search hit type: {}", object_type);
+            return Err(EntityError::internal(message));
+        };
+        let hit = Self {
+            node,
+            score,
+            highlights,
+            cursor: encode_cursor(offset),
+        };
+        Ok(hit)
+    }
+}
+
+fn encode_cursor(offset: u32) -> String {
+    encode_base64(format!("offset:{}", offset))
+}
+
+fn decode_cursor(cursor: &str) -> FieldResult<u32> {
+    let invalid = || field_error(ErrorCode::InvalidInput, "invalid cursor");
+    let raw = decode_base64(cursor).map_err(|_| invalid())?;
+    let raw = String::from_utf8(raw).map_err(|_| invalid())?;
+    let offset = raw.strip_prefix("offset:").ok_or_else(invalid)?;
+    offset.parse().map_err(|_| invalid())
+}
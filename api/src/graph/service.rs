@@ -0,0 +1,303 @@
+use super::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+#[graphql(name = "Scope", remote = "Scope")]
+pub enum ScopeEnum {
+    UsersRead,
+    UsersWrite,
+    MembershipsRead,
+    MembershipsWrite,
+}
+
+entity_object! {
+    #[graphql(name = "ServiceAccount", complex)]
+    pub struct ServiceAccountObject(ServiceAccount) {
+        name: String,
+        description: String,
+    }
+}
+
+#[ComplexObject]
+impl ServiceAccountObject {
+    async fn creator(&self, ctx: &Context<'_>) -> FieldResult<UserObject> {
+        let user = self
+            .entity
+            .creator()
+            .load(ctx.entity())
+            .await
+            .extend("failed to load user")?
+            .ensure(ErrorCode::NotFound, "user not found")?;
+        let user = UserObject::from(user);
+        Ok(user)
+    }
+
+    async fn api_keys(
+        &self,
+        ctx: &Context<'_>,
+    ) -> FieldResult<Vec<ApiKeyObject>> {
+        let keys = self
+            .entity
+            .api_keys()
+            .find(ctx.entity())
+            .await
+            .extend("failed to find API keys")?;
+        let keys: Vec<_> =
+            keys.try_collect().await.extend("failed to load API keys")?;
+        let keys: Vec<_> = keys.into_iter().map(ApiKeyObject::from).collect();
+        Ok(keys)
+    }
+}
+
+entity_object! {
+    #[graphql(name = "ApiKey", complex)]
+    pub struct ApiKeyObject(ApiKey) {
+        name: String,
+        prefix: String,
+    }
+}
+
+#[ComplexObject]
+impl ApiKeyObject {
+    async fn scopes(&self) -> Vec<ScopeEnum> {
+        self.entity.scopes.iter().copied().map(Into::into).collect()
+    }
+
+    async fn expires_at(&self) -> Option<DateTimeScalar> {
+        self.entity.expires_at.map(Into::into)
+    }
+
+    async fn last_used_at(&self) -> Option<DateTimeScalar> {
+        self.entity.last_used_at.map(Into::into)
+    }
+
+    async fn revoked_at(&self) -> Option<DateTimeScalar> {
+        self.entity.revoked_at.map(Into::into)
+    }
+
+    async fn is_active(&self) -> bool {
+        self.entity.is_active()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ServiceQueries;
+
+#[Object]
+impl ServiceQueries {
+    /// The service account making the request, if any.
+    async fn service_viewer(
+        &self,
+        ctx: &Context<'_>,
+    ) -> Option<ServiceAccountObject> {
+        let identity: &Identity = ctx.data_opt()?;
+        let service = identity.service()?;
+        let account = ServiceAccountObject::from(service.account.clone());
+        Some(account)
+    }
+
+    async fn service_accounts(
+        &self,
+        ctx: &Context<'_>,
+    ) -> FieldResult<Vec<ServiceAccountObject>> {
+        with_admin(ctx).await?;
+
+        let accounts = ServiceAccount::all()
+            .find(ctx.entity())
+            .await
+            .extend("failed to find service accounts")?;
+        let accounts: Vec<_> = accounts
+            .try_collect()
+            .await
+            .extend("failed to load service accounts")?;
+        let accounts: Vec<_> = accounts
+            .into_iter()
+            .map(ServiceAccountObject::from)
+            .collect();
+        Ok(accounts)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ServiceMutations;
+
+#[Object]
+impl ServiceMutations {
+    async fn create_service_account(
+        &self,
+        ctx: &Context<'_>,
+        input: CreateServiceAccountInput,
+    ) -> FieldResult<CreateServiceAccountPayload> {
+        let CreateServiceAccountInput { name, description } = input;
+
+        let viewer = with_admin(ctx).await?;
+        let mut account = ServiceAccount::builder()
+            .name(name)
+            .description(description)
+            .creator(viewer.object_ref())
+            .build();
+        account
+            .save(ctx.entity())
+            .await
+            .extend("failed to save service account")?;
+
+        let account = ServiceAccountObject::from(account);
+        let payload = CreateServiceAccountPayload { account };
+        Ok(payload)
+    }
+
+    async fn delete_service_account(
+        &self,
+        ctx: &Context<'_>,
+        input: DeleteServiceAccountInput,
+    ) -> FieldResult<DeleteServiceAccountPayload> {
+        let DeleteServiceAccountInput { account_id } = input;
+
+        with_admin(ctx).await?;
+        let account_id = account_id
+            .get::<ServiceAccount>()
+            .ensure(ErrorCode::InvalidInput, "invalid service account ID")?;
+        let mut account = ServiceAccount::find(&account_id)
+            .load(ctx.entity())
+            .await
+            .extend("failed to load service account")?
+            .ensure(ErrorCode::NotFound, "service account not found")?;
+        account
+            .delete(ctx.entity())
+            .await
+            .extend("failed to delete service account")?;
+
+        let payload = DeleteServiceAccountPayload {
+            account_id: account.global_id().into(),
+        };
+        Ok(payload)
+    }
+
+    async fn create_api_key(
+        &self,
+        ctx: &Context<'_>,
+        input: CreateApiKeyInput,
+    ) -> FieldResult<CreateApiKeyPayload> {
+        let CreateApiKeyInput {
+            account_id,
+            name,
+            scopes,
+            expires_at,
+        } = input;
+
+        let viewer = with_admin(ctx).await?;
+        let account_ref: ObjectRef = account_id
+            .get::<ServiceAccount>()
+            .ensure(ErrorCode::InvalidInput, "invalid service account ID")?
+            .into();
+        let scopes: Vec<Scope> = scopes.into_iter().map(Into::into).collect();
+        let ungranted = scopes.iter().find(|scope| !scope.is_held_by(&viewer));
+        if let Some(scope) = ungranted {
+            let message = format!("can't grant unheld scope: {}", scope);
+            return Err(field_error(ErrorCode::Forbidden, message));
+        }
+        let expires_at: Option<DateTime> = expires_at.map(Into::into);
+        if let Some(expires_at) = expires_at {
+            if expires_at <= Utc::now() {
+                let error = field_error(
+                    ErrorCode::InvalidInput,
+                    "API key must expire in the future",
+                );
+                return Err(error);
+            }
+        }
+
+        let (key, prefix, secret_hash) = ApiKey::generate();
+        let mut api_key = ApiKey::builder()
+            .account(account_ref)
+            .name(name)
+            .prefix(prefix)
+            .secret_hash(secret_hash)
+            .scopes(scopes)
+            .expires_at(expires_at)
+            .build();
+        api_key
+            .save(ctx.entity())
+            .await
+            .extend("failed to save API key")?;
+
+        let api_key = ApiKeyObject::from(api_key);
+        let payload = CreateApiKeyPayload { api_key, key };
+        Ok(payload)
+    }
+
+    async fn revoke_api_key(
+        &self,
+        ctx: &Context<'_>,
+        input: RevokeApiKeyInput,
+    ) -> FieldResult<RevokeApiKeyPayload> {
+        let RevokeApiKeyInput { api_key_id } = input;
+
+        with_admin(ctx).await?;
+        let mut api_key = {
+            let id = api_key_id
+                .get::<ApiKey>()
+                .ensure(ErrorCode::InvalidInput, "invalid API key ID")?;
+            ApiKey::find(&id)
+                .load(ctx.entity())
+                .await
+                .extend("failed to load API key")?
+                .ensure(ErrorCode::NotFound, "API key not found")?
+        };
+        api_key.revoke().extend("failed to revoke API key")?;
+        api_key
+            .save(ctx.entity())
+            .await
+            .extend("failed to save API key")?;
+
+        let api_key = ApiKeyObject::from(api_key);
+        let payload = RevokeApiKeyPayload { api_key };
+        Ok(payload)
+    }
+}
+
+#[derive(Debug, Clone, InputObject)]
+struct CreateServiceAccountInput {
+    name: String,
+    description: String,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+struct CreateServiceAccountPayload {
+    account: ServiceAccountObject,
+}
+
+#[derive(Debug, Clone, InputObject)]
+struct DeleteServiceAccountInput {
+    account_id: NodeId,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+struct DeleteServiceAccountPayload {
+    account_id: NodeId,
+}
+
+#[derive(Debug, Clone, InputObject)]
+struct CreateApiKeyInput {
+    account_id: NodeId,
+    name: String,
+    scopes: Vec<ScopeEnum>,
+    expires_at: Option<DateTimeScalar>,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+struct CreateApiKeyPayload {
+    api_key: ApiKeyObject,
+
+    /// The key to authenticate with. It can't be retrieved again.
+    key: String,
+}
+
+#[derive(Debug, Clone, InputObject)]
+struct RevokeApiKeyInput {
+    api_key_id: NodeId,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+struct RevokeApiKeyPayload {
+    api_key: ApiKeyObject,
+}
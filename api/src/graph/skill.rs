@@ -0,0 +1,318 @@
+use super::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+#[graphql(name = "SkillCategory", remote = "SkillCategory")]
+pub enum SkillCategoryEnum {
+    Language,
+    Framework,
+    Platform,
+    Design,
+    Product,
+    Other,
+}
+
+entity_object! {
+    #[graphql(name = "Skill")]
+    pub struct SkillObject(Skill) {
+        name: String,
+        category: SkillCategoryEnum,
+        aliases: Vec<String>,
+    }
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name = "UserSkill", complex)]
+pub struct UserSkillObject {
+    #[graphql(skip)]
+    pub entity: UserSkill,
+
+    pub rating: i32,
+    pub last_used: Option<DateScalar>,
+}
+
+impl From<UserSkill> for UserSkillObject {
+    fn from(skill: UserSkill) -> Self {
+        Self {
+            rating: skill.rating.into(),
+            last_used: skill.last_used.map(Into::into),
+            entity: skill,
+        }
+    }
+}
+
+#[ComplexObject]
+impl UserSkillObject {
+    async fn skill(&self, ctx: &Context<'_>) -> FieldResult<SkillObject> {
+        let skill = self
+            .entity
+            .skill()
+            .load(ctx.entity())
+            .await
+            .extend("failed to load skill")?
+            .ensure(ErrorCode::NotFound, "skill not found")?;
+        let skill = SkillObject::from(skill);
+        Ok(skill)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SkillQueries;
+
+#[Object]
+impl SkillQueries {
+    /// All skills, optionally in one category. Available to every account.
+    async fn skills(
+        &self,
+        ctx: &Context<'_>,
+        category: Option<SkillCategoryEnum>,
+    ) -> FieldResult<Vec<SkillObject>> {
+        let conditions = SkillConditions::builder()
+            .category(category.map(Into::into))
+            .build();
+        let skills = Skill::filter(conditions)
+            .find(ctx.entity())
+            .await
+            .extend("failed to find skills")?;
+        let skills: Vec<_> =
+            skills.try_collect().await.extend("failed to load skills")?;
+        let skills: Vec<_> =
+            skills.into_iter().map(SkillObject::from).collect();
+        Ok(skills)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SkillMutations;
+
+#[Object]
+impl SkillMutations {
+    async fn create_skill(
+        &self,
+        ctx: &Context<'_>,
+        input: CreateSkillInput,
+    ) -> FieldResult<CreateSkillPayload> {
+        with_admin(ctx).await?;
+
+        let CreateSkillInput {
+            name,
+            category,
+            aliases,
+        } = input;
+
+        let mut skill = Skill::builder()
+            .name(name)
+            .category(category)
+            .aliases(aliases.unwrap_or_default())
+            .build();
+        skill
+            .save(ctx.entity())
+            .await
+            .extend("failed to save skill")?;
+
+        let skill = SkillObject::from(skill);
+        let payload = CreateSkillPayload { skill };
+        Ok(payload)
+    }
+
+    async fn update_skill(
+        &self,
+        ctx: &Context<'_>,
+        input: UpdateSkillInput,
+    ) -> FieldResult<UpdateSkillPayload> {
+        with_admin(ctx).await?;
+
+        let UpdateSkillInput {
+            skill_id,
+            name,
+            category,
+            aliases,
+        } = input;
+
+        let skill = {
+            let id = skill_id
+                .get::<Skill>()
+                .ensure(ErrorCode::InvalidInput, "invalid skill ID")?;
+            Skill::find(&id)
+                .load(ctx.entity())
+                .await
+                .extend("failed to load skill")?
+                .ensure(ErrorCode::NotFound, "skill not found")?
+        };
+
+        let mut skill = Skill {
+            name,
+            category: category.into(),
+            ..skill
+        };
+        if let Some(aliases) = aliases {
+            skill.aliases = aliases;
+        }
+        skill
+            .save(ctx.entity())
+            .await
+            .extend("failed to save skill")?;
+
+        let skill = SkillObject::from(skill);
+        let payload = UpdateSkillPayload { skill };
+        Ok(payload)
+    }
+
+    async fn delete_skill(
+        &self,
+        ctx: &Context<'_>,
+        input: DeleteSkillInput,
+    ) -> FieldResult<DeleteSkillPayload> {
+        with_admin(ctx).await?;
+
+        let DeleteSkillInput { skill_id } = input;
+
+        let skill_id = skill_id
+            .get::<Skill>()
+            .ensure(ErrorCode::InvalidInput, "invalid skill ID")?;
+        let mut skill = Skill::find(&skill_id)
+            .load(ctx.entity())
+            .await
+            .extend("failed to load skill")?
+            .ensure(ErrorCode::NotFound, "skill not found")?;
+        skill
+            .delete(ctx.entity())
+            .await
+            .extend("failed to delete skill")?;
+
+        let payload = DeleteSkillPayload {
+            skill_id: skill.global_id().into(),
+        };
+        Ok(payload)
+    }
+
+    async fn set_user_skill(
+        &self,
+        ctx: &Context<'_>,
+        input: SetUserSkillInput,
+    ) -> FieldResult<SetUserSkillPayload> {
+        let SetUserSkillInput {
+            user_id,
+            skill_id,
+            rating,
+            last_used,
+        } = input;
+
+        let mut user = with_writable_user(ctx, user_id).await?;
+        let skill_ref: ObjectRef = skill_id
+            .get::<Skill>()
+            .ensure(ErrorCode::InvalidInput, "invalid skill ID")?
+            .into();
+        let rating = u8::try_from(rating).map_err(|_| {
+            field_error(ErrorCode::InvalidInput, "invalid skill rating")
+        })?;
+
+        let skill = UserSkill::builder()
+            .skill(skill_ref.clone())
+            .rating(rating)
+            .last_used(last_used.map(Into::into))
+            .build();
+        match user
+            .skills
+            .iter_mut()
+            .find(|skill| skill.skill == skill_ref)
+        {
+            Some(existing) => *existing = skill,
+            None => user.skills.push(skill),
+        }
+        user.save(ctx.entity())
+            .await
+            .extend("failed to save user")?;
+
+        let user = UserObject::from(user);
+        let payload = SetUserSkillPayload { user };
+        Ok(payload)
+    }
+
+    async fn remove_user_skill(
+        &self,
+        ctx: &Context<'_>,
+        input: RemoveUserSkillInput,
+    ) -> FieldResult<RemoveUserSkillPayload> {
+        let RemoveUserSkillInput { user_id, skill_id } = input;
+
+        let mut user = with_writable_user(ctx, user_id).await?;
+        let skill_ref: ObjectRef = skill_id
+            .get::<Skill>()
+            .ensure(ErrorCode::InvalidInput, "invalid skill ID")?
+            .into();
+
+        user.skills.retain(|skill| skill.skill != skill_ref);
+        user.save(ctx.entity())
+            .await
+            .extend("failed to save user")?;
+
+        let user = UserObject::from(user);
+        let payload = RemoveUserSkillPayload { user };
+        Ok(payload)
+    }
+}
+
+#[derive(Debug, Clone, InputObject)]
+struct CreateSkillInput {
+    name: String,
+    category: SkillCategoryEnum,
+
+    /// Other names the skill can be looked up by. Its own name is always
+    /// included.
+    aliases: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+struct CreateSkillPayload {
+    skill: SkillObject,
+}
+
+#[derive(Debug, Clone, InputObject)]
+struct UpdateSkillInput {
+    skill_id: NodeId,
+    name: String,
+    category: SkillCategoryEnum,
+
+    /// Replaces the skill's aliases, if set. Its own name is always
+    /// included.
+    aliases: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+struct UpdateSkillPayload {
+    skill: SkillObject,
+}
+
+#[derive(Debug, Clone, InputObject)]
+struct DeleteSkillInput {
+    skill_id: NodeId,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+struct DeleteSkillPayload {
+    skill_id: NodeId,
+}
+
+#[derive(Debug, Clone, InputObject)]
+struct SetUserSkillInput {
+    user_id: NodeId,
+    skill_id: NodeId,
+    rating: i32,
+    last_used: Option<DateScalar>,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+struct SetUserSkillPayload {
+    user: UserObject,
+}
+
+#[derive(Debug, Clone, InputObject)]
+struct RemoveUserSkillInput {
+    user_id: NodeId,
+    skill_id: NodeId,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+struct RemoveUserSkillPayload {
+    user: UserObject,
+}
@@ -0,0 +1,320 @@
+use super::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+#[graphql(name = "TeamKind", remote = "TeamKind")]
+pub enum TeamKindEnum {
+    Department,
+    Team,
+}
+
+entity_object! {
+    #[graphql(name = "Team", complex)]
+    pub struct TeamObject(Team) {
+        kind: TeamKindEnum,
+        name: String,
+        description: String,
+    }
+}
+
+#[ComplexObject]
+impl TeamObject {
+    async fn parent(
+        &self,
+        ctx: &Context<'_>,
+    ) -> FieldResult<Option<TeamObject>> {
+        let parent = match self.entity.parent() {
+            Some(query) => query
+                .load(ctx.entity())
+                .await
+                .extend("failed to load parent team")?,
+            None => None,
+        };
+        let parent = parent.map(TeamObject::from);
+        Ok(parent)
+    }
+
+    async fn children(
+        &self,
+        ctx: &Context<'_>,
+    ) -> FieldResult<Vec<TeamObject>> {
+        let teams = self
+            .entity
+            .children()
+            .find(ctx.entity())
+            .await
+            .extend("failed to find teams")?;
+        let teams: Vec<_> =
+            teams.try_collect().await.extend("failed to load teams")?;
+        let teams: Vec<_> = teams.into_iter().map(TeamObject::from).collect();
+        Ok(teams)
+    }
+
+    async fn roles(
+        &self,
+        ctx: &Context<'_>,
+    ) -> FieldResult<Vec<MemberRoleObject>> {
+        let roles = self
+            .entity
+            .roles()
+            .find(ctx.entity())
+            .await
+            .extend("failed to find member roles")?;
+        let roles: Vec<_> = roles
+            .try_collect()
+            .await
+            .extend("failed to load member roles")?;
+        let roles: Vec<_> =
+            roles.into_iter().map(MemberRoleObject::from).collect();
+        Ok(roles)
+    }
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name = "OrgChart")]
+pub struct OrgChartObject {
+    pub teams: Vec<OrgChartTeamObject>,
+    pub roles: Vec<OrgChartRoleObject>,
+}
+
+impl From<OrgChart> for OrgChartObject {
+    fn from(chart: OrgChart) -> Self {
+        let OrgChart { teams, roles } = chart;
+        Self {
+            teams: teams.into_iter().map(Into::into).collect(),
+            roles: roles.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name = "OrgChartTeam")]
+pub struct OrgChartTeamObject {
+    pub team: TeamObject,
+    pub roles: Vec<OrgChartRoleObject>,
+    pub children: Vec<OrgChartTeamObject>,
+}
+
+impl From<OrgChartTeam> for OrgChartTeamObject {
+    fn from(team: OrgChartTeam) -> Self {
+        let OrgChartTeam {
+            team,
+            roles,
+            children,
+        } = team;
+        Self {
+            team: team.into(),
+            roles: roles.into_iter().map(Into::into).collect(),
+            children: children.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name = "OrgChartRole")]
+pub struct OrgChartRoleObject {
+    pub role: MemberRoleObject,
+    pub members: Vec<UserObject>,
+}
+
+impl From<OrgChartRole> for OrgChartRoleObject {
+    fn from(role: OrgChartRole) -> Self {
+        let OrgChartRole { role, members } = role;
+        Self {
+            role: role.into(),
+            members: members.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TeamQueries;
+
+#[Object]
+impl TeamQueries {
+    /// All teams. Like terms, skills, and member roles, teams are available
+    /// to every account, since they don't describe anyone in particular.
+    async fn teams(&self, ctx: &Context<'_>) -> FieldResult<Vec<TeamObject>> {
+        let teams = Team::all()
+            .find(ctx.entity())
+            .await
+            .extend("failed to find teams")?;
+        let teams: Vec<_> =
+            teams.try_collect().await.extend("failed to load teams")?;
+        let teams: Vec<_> = teams.into_iter().map(TeamObject::from).collect();
+        Ok(teams)
+    }
+
+    async fn org_chart(
+        &self,
+        ctx: &Context<'_>,
+        date: Option<DateScalar>,
+    ) -> FieldResult<OrgChartObject> {
+        authorize_member(ctx, Scope::MembershipsRead).await?;
+
+        let date: Date = match date {
+            Some(date) => date.into(),
+            None => Utc::today().naive_utc(),
+        };
+        let chart = OrgChart::build(ctx.entity(), date)
+            .await
+            .extend("failed to build org chart")?;
+        let chart = OrgChartObject::from(chart);
+        Ok(chart)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TeamMutations;
+
+#[Object]
+impl TeamMutations {
+    async fn create_team(
+        &self,
+        ctx: &Context<'_>,
+        input: CreateTeamInput,
+    ) -> FieldResult<CreateTeamPayload> {
+        with_admin(ctx).await?;
+
+        let CreateTeamInput {
+            kind,
+            name,
+            description,
+            parent_id,
+        } = input;
+        let parent_ref = team_ref(parent_id)?;
+
+        let mut team = Team::builder()
+            .kind(kind)
+            .name(name)
+            .description(description)
+            .parent(parent_ref)
+            .build();
+        team.save(ctx.entity())
+            .await
+            .extend("failed to save team")?;
+
+        let team = TeamObject::from(team);
+        let payload = CreateTeamPayload { team };
+        Ok(payload)
+    }
+
+    async fn update_team(
+        &self,
+        ctx: &Context<'_>,
+        input: UpdateTeamInput,
+    ) -> FieldResult<UpdateTeamPayload> {
+        with_admin(ctx).await?;
+
+        let UpdateTeamInput {
+            team_id,
+            kind,
+            name,
+            description,
+            parent_id,
+        } = input;
+
+        let team = {
+            let id = team_id
+                .get::<Team>()
+                .ensure(ErrorCode::InvalidInput, "invalid team ID")?;
+            Team::find(&id)
+                .load(ctx.entity())
+                .await
+                .extend("failed to load team")?
+                .ensure(ErrorCode::NotFound, "team not found")?
+        };
+        let parent_ref = team_ref(parent_id)?;
+
+        let mut team = Team {
+            kind: kind.into(),
+            name,
+            description,
+            parent: parent_ref,
+            ..team
+        };
+        team.save(ctx.entity())
+            .await
+            .extend("failed to save team")?;
+
+        let team = TeamObject::from(team);
+        let payload = UpdateTeamPayload { team };
+        Ok(payload)
+    }
+
+    async fn delete_team(
+        &self,
+        ctx: &Context<'_>,
+        input: DeleteTeamInput,
+    ) -> FieldResult<DeleteTeamPayload> {
+        with_admin(ctx).await?;
+
+        let DeleteTeamInput { team_id } = input;
+
+        let team_id = team_id
+            .get::<Team>()
+            .ensure(ErrorCode::InvalidInput, "invalid team ID")?;
+        let mut team = Team::find(&team_id)
+            .load(ctx.entity())
+            .await
+            .extend("failed to load team")?
+            .ensure(ErrorCode::NotFound, "team not found")?;
+        team.delete(ctx.entity())
+            .await
+            .extend("failed to delete team")?;
+
+        let payload = DeleteTeamPayload {
+            team_id: team.global_id().into(),
+        };
+        Ok(payload)
+    }
+}
+
+pub(super) fn team_ref(
+    team_id: Option<NodeId>,
+) -> FieldResult<Option<ObjectRef>> {
+    let team_id = match team_id {
+        Some(team_id) => team_id,
+        None => return Ok(None),
+    };
+    let id = team_id
+        .get::<Team>()
+        .ensure(ErrorCode::InvalidInput, "invalid team ID")?;
+    Ok(Some(id.into()))
+}
+
+#[derive(Debug, Clone, InputObject)]
+struct CreateTeamInput {
+    kind: TeamKindEnum,
+    name: String,
+    description: String,
+    parent_id: Option<NodeId>,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+struct CreateTeamPayload {
+    team: TeamObject,
+}
+
+#[derive(Debug, Clone, InputObject)]
+struct UpdateTeamInput {
+    team_id: NodeId,
+    kind: TeamKindEnum,
+    name: String,
+    description: String,
+    parent_id: Option<NodeId>,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+struct UpdateTeamPayload {
+    team: TeamObject,
+}
+
+#[derive(Debug, Clone, InputObject)]
+struct DeleteTeamInput {
+    team_id: NodeId,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+struct DeleteTeamPayload {
+    team_id: NodeId,
+}
@@ -0,0 +1,153 @@
+use super::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+#[graphql(name = "Season", remote = "Season")]
+pub enum SeasonEnum {
+    Winter,
+    Spring,
+    Fall,
+}
+
+entity_object! {
+    #[graphql(name = "Term", complex)]
+    pub struct TermObject(Term) {
+        season: SeasonEnum,
+        year: i32,
+        start: DateScalar,
+        end: DateScalar,
+    }
+}
+
+#[ComplexObject]
+impl TermObject {
+    async fn name(&self) -> String {
+        self.entity.name()
+    }
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name = "TermName")]
+pub struct TermNameObject {
+    pub season: SeasonEnum,
+    pub year: i32,
+    pub name: String,
+}
+
+impl From<TermName> for TermNameObject {
+    fn from(term: TermName) -> Self {
+        Self {
+            season: term.season.into(),
+            year: term.year,
+            name: term.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, InputObject)]
+pub struct TermNameInput {
+    pub season: SeasonEnum,
+    pub year: i32,
+}
+
+impl From<TermNameInput> for TermName {
+    fn from(input: TermNameInput) -> Self {
+        let TermNameInput { season, year } = input;
+        TermName::new(season.into(), year)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TermQueries;
+
+#[Object]
+impl TermQueries {
+    /// All terms, in order. Available to every account.
+    async fn terms(&self, ctx: &Context<'_>) -> FieldResult<Vec<TermObject>> {
+        let terms = Term::all()
+            .sort(TermSorting::Start(SortingOrder::Asc))
+            .find(ctx.entity())
+            .await
+            .extend("failed to find terms")?;
+        let terms: Vec<_> =
+            terms.try_collect().await.extend("failed to load terms")?;
+        let terms: Vec<_> = terms.into_iter().map(TermObject::from).collect();
+        Ok(terms)
+    }
+
+    async fn current_term(
+        &self,
+        ctx: &Context<'_>,
+    ) -> FieldResult<Option<TermObject>> {
+        let today = Utc::today().naive_utc();
+        let term = Term::find_at(today)
+            .load(ctx.entity())
+            .await
+            .extend("failed to load term")?;
+        let term = term.map(TermObject::from);
+        Ok(term)
+    }
+
+    async fn next_term(
+        &self,
+        ctx: &Context<'_>,
+    ) -> FieldResult<Option<TermObject>> {
+        let today = Utc::today().naive_utc();
+        let terms = Term::after(today)
+            .take(1)
+            .find(ctx.entity())
+            .await
+            .extend("failed to find terms")?;
+        let terms: Vec<_> =
+            terms.try_collect().await.extend("failed to load terms")?;
+        let term = terms.into_iter().next().map(TermObject::from);
+        Ok(term)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TermMutations;
+
+#[Object]
+impl TermMutations {
+    async fn create_term(
+        &self,
+        ctx: &Context<'_>,
+        input: CreateTermInput,
+    ) -> FieldResult<CreateTermPayload> {
+        with_admin(ctx).await?;
+
+        let CreateTermInput {
+            season,
+            year,
+            start,
+            end,
+        } = input;
+
+        let mut term = Term::builder()
+            .season(season)
+            .year(year)
+            .start(start)
+            .end(end)
+            .build();
+        term.save(ctx.entity())
+            .await
+            .extend("failed to save term")?;
+
+        let term = TermObject::from(term);
+        let payload = CreateTermPayload { term };
+        Ok(payload)
+    }
+}
+
+#[derive(Debug, Clone, InputObject)]
+struct CreateTermInput {
+    season: SeasonEnum,
+    year: i32,
+    start: DateScalar,
+    end: DateScalar,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+struct CreateTermPayload {
+    term: TermObject,
+}
@@ -1,63 +1,66 @@
 use super::prelude::*;
 
-#[derive(Debug, Clone, From, Deref)]
-pub struct UserObject(User);
-
-// TODO: Use macros to generate simple getters.
-#[Object(name = "User")]
-impl UserObject {
-    async fn id(&self) -> NodeId {
-        self.global_id().into()
-    }
-
-    async fn created_at(&self) -> DateTimeScalar {
-        let date_time = self.created_at.clone();
-        date_time.into()
-    }
-
-    async fn updated_at(&self) -> DateTimeScalar {
-        let date_time = self.updated_at.clone();
-        date_time.into()
-    }
+use std::io::Error as IoError;
+use std::io::Read;
+use tokio::task::spawn_blocking;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+#[graphql(name = "AccountType", remote = "AccountType")]
+pub enum AccountTypeEnum {
+    Member,
+    PartnerContact,
+    Alumni,
+}
 
-    async fn first_name(&self) -> &String {
-        &self.first_name
+entity_object! {
+    #[graphql(name = "User", complex)]
+    pub struct UserObject(User) {
+        first_name: String,
+        last_name: String,
+        preferred_name: Option<String>,
+        pronouns: Option<String>,
+        email: String,
+        account_type: AccountTypeEnum,
+        is_admin: bool,
+        photo_url: Option<String>,
+        website_url: Option<String>,
+        twitter_handle: Option<String>,
+        instagram_handle: Option<String>,
+        bio: Option<String>,
+        program: Option<String>,
+        year: Option<u8>,
     }
+}
 
-    async fn last_name(&self) -> &String {
-        &self.last_name
+#[ComplexObject]
+impl UserObject {
+    async fn display_name(&self) -> &str {
+        self.entity.display_name()
     }
 
     async fn full_name(&self) -> String {
-        format!("{} {}", &self.first_name, &self.last_name)
-    }
-
-    async fn email(&self) -> &String {
-        &self.email
-    }
-
-    async fn phone(&self) -> Option<&String> {
-        self.phone.as_ref()
-    }
-
-    async fn photo_url(&self) -> Option<&String> {
-        self.photo_url.as_ref()
-    }
-
-    async fn website_url(&self) -> Option<&String> {
-        self.website_url.as_ref()
-    }
-
-    async fn twitter_handle(&self) -> Option<&String> {
-        self.twitter_handle.as_ref()
+        self.entity.full_name()
     }
 
-    async fn instagram_handle(&self) -> Option<&String> {
-        self.instagram_handle.as_ref()
+    async fn graduation_term(&self) -> Option<TermNameObject> {
+        self.entity.graduation_term.map(TermNameObject::from)
     }
 
-    async fn bio(&self) -> Option<&String> {
-        self.bio.as_ref()
+    /// The user's phone number. Only visible to the user themselves, to
+    /// members, and to service accounts that may read users.
+    async fn phone(&self, ctx: &Context<'_>) -> FieldResult<Option<String>> {
+        if !with_service_scope(ctx, Scope::UsersRead)? {
+            let viewer = with_viewer(ctx).await?;
+            let is_self = viewer.id == self.entity.id;
+            if !is_self && !viewer.account_type.is_member() {
+                let error = field_error(
+                    ErrorCode::Forbidden,
+                    "not available to non-member accounts",
+                );
+                return Err(error);
+            }
+        }
+        Ok(self.entity.phone.clone())
     }
 
     async fn memberships(
@@ -65,7 +68,7 @@ impl UserObject {
         ctx: &Context<'_>,
     ) -> FieldResult<Vec<MembershipObject>> {
         let memberships = self
-            .0
+            .entity
             .memberships()
             .sort(MembershipSorting::Start(SortingOrder::Desc))
             .sort(MembershipSorting::End(SortingOrder::Desc))
@@ -82,6 +85,51 @@ impl UserObject {
             .collect();
         Ok(memberships)
     }
+
+    /// The identities the user signs in with. Only visible to the user
+    /// themselves.
+    async fn identity_links(
+        &self,
+        ctx: &Context<'_>,
+    ) -> FieldResult<Vec<IdentityLinkObject>> {
+        let viewer = with_viewer(ctx).await?;
+        if viewer.id != self.entity.id {
+            let error = field_error(ErrorCode::Forbidden, "not authorized");
+            return Err(error);
+        }
+
+        let links = self
+            .entity
+            .identity_links()
+            .find(ctx.entity())
+            .await
+            .extend("failed to find identity links")?;
+        let links: Vec<_> = links
+            .try_collect()
+            .await
+            .extend("failed to load identity links")?;
+        let links: Vec<_> =
+            links.into_iter().map(IdentityLinkObject::from).collect();
+        Ok(links)
+    }
+
+    async fn skills(&self) -> Vec<UserSkillObject> {
+        let skills = self.entity.skills.clone();
+        skills.into_iter().map(UserSkillObject::from).collect()
+    }
+
+    async fn is_active_member(&self, ctx: &Context<'_>) -> FieldResult<bool> {
+        let today = Utc::today().naive_utc();
+        let conditions = MembershipConditions::builder()
+            .user(self.entity.object_ref())
+            .active_at(today)
+            .build();
+        let count = Membership::filter(conditions)
+            .count(ctx.entity())
+            .await
+            .extend("failed to count memberships")?;
+        Ok(count.is_positive())
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -93,17 +141,19 @@ impl UserQueries {
         &self,
         ctx: &Context<'_>,
     ) -> FieldResult<Option<UserObject>> {
-        let email = match ctx.data_opt::<Identity>() {
-            Some(identity) => {
-                let IdentityClaims { email, .. } = identity.claims();
-                email.as_str()
-            }
+        let identity: &Identity = match ctx.data_opt() {
+            Some(identity) => identity,
             None => return Ok(None),
         };
-        let user = User::find_by_email(email)
-            .load(ctx.entity())
-            .await
-            .extend("failed to load user")?;
+        if let Some(impersonation) = identity.impersonation() {
+            let user = UserObject::from(impersonation.target.clone());
+            return Ok(Some(user));
+        }
+        let claims = match identity.claims() {
+            Some(claims) => claims,
+            None => return Ok(None),
+        };
+        let user = load_viewer(ctx, claims).await?;
         let user = user.map(UserObject::from);
         Ok(user)
     }
@@ -113,8 +163,45 @@ impl UserQueries {
         &self,
         ctx: &Context<'_>,
         query: Option<String>,
+        skills: Option<Vec<String>>,
     ) -> FieldResult<Vec<UserObject>> {
-        let conditions = UserConditions::builder().query(query).build();
+        authorize_member(ctx, Scope::UsersRead).await?;
+
+        let skill_refs: Option<Vec<ObjectRef>> = match skills {
+            Some(names) => {
+                let mut names: Vec<String> = names
+                    .iter()
+                    .map(|name| name.trim().to_lowercase())
+                    .collect();
+                names.sort();
+                names.dedup();
+                let skills = Skill::named(names.clone())
+                    .find(ctx.entity())
+                    .await
+                    .extend("failed to find skills")?;
+                let skills: Vec<Skill> = skills
+                    .try_collect()
+                    .await
+                    .extend("failed to load skills")?;
+
+                // No user can have a skill that doesn't exist. Several names
+                // may be aliases of the same skill, so match names to skills
+                // rather than counting them.
+                let all_exist = names.iter().all(|name| {
+                    skills.iter().any(|skill| skill.aliases.contains(name))
+                });
+                if !all_exist {
+                    return Ok(Vec::new());
+                }
+                let refs = skills.iter().map(Skill::object_ref).collect();
+                Some(refs)
+            }
+            None => None,
+        };
+        let conditions = UserConditions::builder()
+            .query(query)
+            .skills(skill_refs)
+            .build();
         let users = User::filter(conditions)
             .find(ctx.entity())
             .await
@@ -124,6 +211,51 @@ impl UserQueries {
         let users: Vec<_> = users.into_iter().map(UserObject::from).collect();
         Ok(users)
     }
+
+    async fn search_users(
+        &self,
+        ctx: &Context<'_>,
+        prefix: String,
+        first: Option<i32>,
+    ) -> FieldResult<Vec<UserSearchResult>> {
+        authorize_member(ctx, Scope::UsersRead).await?;
+
+        let limit = search_limit(first)?;
+        let results = User::search(prefix)
+            .take(limit)
+            .find(ctx.entity())
+            .await
+            .extend("failed to search users")?;
+        let results: Vec<_> =
+            results.into_iter().map(UserSearchResult::from).collect();
+        Ok(results)
+    }
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub struct UserSearchResult {
+    pub user: UserObject,
+    pub score: i32,
+    pub highlights: Vec<SearchHighlightObject>,
+}
+
+impl From<SearchResult<User>> for UserSearchResult {
+    fn from(result: SearchResult<User>) -> Self {
+        let SearchResult {
+            entity,
+            score,
+            highlights,
+        } = result;
+        let highlights = highlights
+            .into_iter()
+            .map(SearchHighlightObject::from)
+            .collect();
+        Self {
+            user: entity.into(),
+            score,
+            highlights,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -136,30 +268,103 @@ impl UserMutations {
         ctx: &Context<'_>,
         input: RegisterUserInput,
     ) -> FieldResult<RegisterUserPayload> {
-        let IdentityClaims { email, .. } = with_identity(ctx)?;
-        if !email.ends_with("@uwblueprint.org") {
-            let error = format_err!("invalid email domain");
-            return Err(error.into());
+        let claims = with_identity(ctx)?;
+        let IdentityClaims {
+            email,
+            email_verified,
+            ..
+        } = claims;
+        if !email_verified {
+            let error = field_error(ErrorCode::Forbidden, "email not verified");
+            return Err(error);
         }
-
         let RegisterUserInput {
             first_name,
             last_name,
             phone,
             photo_url,
+            invitation_token,
         } = input;
 
-        let existing_user = User::find_by_email(email)
-            .load(ctx.entity())
-            .await
-            .extend("failed to load user")?;
+        // Invitees may register with any email, so long as it's the one they
+        // were invited with.
+        let invitation = match invitation_token {
+            Some(token) => {
+                let invitation = Invitation::find_by_token(&token)
+                    .load(ctx.entity())
+                    .await
+                    .extend("failed to load invitation")?
+                    .ensure(ErrorCode::NotFound, "invitation not found")?;
+                if !invitation.email.eq_ignore_ascii_case(email) {
+                    let error = field_error(
+                        ErrorCode::Forbidden,
+                        "invitation is for a different email",
+                    );
+                    return Err(error);
+                }
+                let status = invitation.status();
+                if status != InvitationStatus::Pending {
+                    let message = format!(
+                        "invitation is {}",
+                        status.to_string().to_lowercase()
+                    );
+                    return Err(field_error(ErrorCode::InvalidInput, message));
+                }
+                Some(invitation)
+            }
+            None => None,
+        };
+
+        // Find the user by their identity, rather than by email, so that
+        // someone who was reassigned another user's email doesn't take over
+        // their account.
+        let mut existing_user = load_viewer(ctx, claims).await?;
+        let is_linked = existing_user.is_some();
+        if !is_linked {
+            // Users who registered before identities were linked have none,
+            // so they're adopted by whoever verifies their email.
+            let user = User::find_by_email(email)
+                .load(ctx.entity())
+                .await
+                .extend("failed to load user")?;
+            if let Some(user) = user {
+                let links = user
+                    .identity_links()
+                    .count(ctx.entity())
+                    .await
+                    .extend("failed to count identity links")?;
+                if links.is_positive() {
+                    let error = field_error(
+                        ErrorCode::Conflict,
+                        "email is registered to another account",
+                    );
+                    return Err(error);
+                }
+                existing_user = Some(user);
+            }
+        }
         let is_new_user = existing_user.is_none();
 
+        // Invitees become members. Otherwise, existing users keep their
+        // account type, and new users get the one their email allows.
+        let account_type = match &existing_user {
+            _ if invitation.is_some() => AccountType::Member,
+            Some(user) => user.account_type,
+            None => {
+                let policy = ctx.data::<EmailPolicy>()?;
+                policy.account_type(email).ensure(
+                    ErrorCode::Forbidden,
+                    "email not allowed to register",
+                )?
+            }
+        };
+
         let mut user = match existing_user {
             Some(user) => User {
                 first_name,
                 last_name,
                 email: email.to_owned(),
+                account_type,
                 phone,
                 photo_url,
                 ..user
@@ -168,16 +373,45 @@ impl UserMutations {
                 .first_name(first_name)
                 .last_name(last_name)
                 .email(email)
+                .account_type(account_type)
                 .phone(phone)
                 .photo_url(photo_url)
                 .build(),
         };
-        user.save(ctx.entity())
-            .await
-            .extend("failed to save user")?;
+
+        // Accept the invitation before saving the user, so that they aren't
+        // saved as a member if it can't be accepted.
+        let accepted = match invitation {
+            Some(mut invitation) => {
+                let membership = invitation
+                    .accept(ctx.entity(), &user)
+                    .await
+                    .extend("failed to accept invitation")?;
+                Some((invitation, membership))
+            }
+            None => None,
+        };
+        if let Err(error) = user.save(ctx.entity()).await {
+            if let Some((mut invitation, membership)) = accepted {
+                invitation
+                    .rollback_accept(ctx.entity(), membership)
+                    .await
+                    .extend("failed to roll back invitation")?;
+            }
+            return Err(error).extend("failed to save user");
+        }
+        if !is_linked {
+            link_identity(ctx, &user, claims).await?;
+        }
+        let membership =
+            accepted.map(|(_, membership)| MembershipObject::from(membership));
 
         let user = UserObject::from(user);
-        let payload = RegisterUserPayload { user, is_new_user };
+        let payload = RegisterUserPayload {
+            user,
+            is_new_user,
+            membership,
+        };
         Ok(payload)
     }
 
@@ -188,25 +422,29 @@ impl UserMutations {
     ) -> FieldResult<UpdateUserPayload> {
         let UpdateUserInput {
             user_id,
+            preferred_name,
+            pronouns,
             bio,
             website_url,
             twitter_handle,
             instagram_handle,
+            program,
+            year,
+            graduation_term,
         } = input;
-        let user_id = user_id.get::<User>().ensure("invalid user ID")?;
-
-        let viewer = with_viewer(ctx).await?;
-        if viewer.id != user_id {
-            let error = format_err!("not authorized");
-            return Err(error.into());
-        }
+        let user = with_writable_user(ctx, user_id).await?;
 
         let mut user = User {
+            preferred_name,
+            pronouns,
             website_url,
             twitter_handle,
             instagram_handle,
             bio,
-            ..viewer
+            program,
+            year,
+            graduation_term: graduation_term.map(Into::into),
+            ..user
         };
         user.save(ctx.entity())
             .await
@@ -216,6 +454,93 @@ impl UserMutations {
         let payload = UpdateUserPayload { user };
         Ok(payload)
     }
+
+    async fn upload_profile_photo(
+        &self,
+        ctx: &Context<'_>,
+        input: UploadProfilePhotoInput,
+    ) -> FieldResult<UploadProfilePhotoPayload> {
+        let UploadProfilePhotoInput { user_id, photo } = input;
+        let user_id = user_id
+            .get::<User>()
+            .ensure(ErrorCode::InvalidInput, "invalid user ID")?;
+
+        let viewer = with_viewer(ctx).await?;
+        if viewer.id != user_id {
+            let error = field_error(ErrorCode::Forbidden, "not authorized");
+            return Err(error);
+        }
+
+        let photo = photo.value(ctx).extend("failed to read photo")?;
+        if !PHOTO_CONTENT_TYPES
+            .contains(&photo.content_type.as_deref().unwrap_or(""))
+        {
+            let message = format!(
+                "photo must be one of: {}",
+                PHOTO_CONTENT_TYPES.join(", ")
+            );
+            return Err(field_error(ErrorCode::InvalidInput, message));
+        }
+
+        // Read the photo, and render its variants.
+        let variants = {
+            let mut content = photo.content;
+            spawn_blocking(move || {
+                let mut data = Vec::new();
+                let limit = MAX_PHOTO_SIZE as u64 + 1;
+                content.by_ref().take(limit).read_to_end(&mut data)?;
+                if data.len() > MAX_PHOTO_SIZE {
+                    return Ok::<_, IoError>(None);
+                }
+                let variants = render_photo_variants(&data);
+                Ok(Some(variants))
+            })
+            .await
+            .extend("failed to process photo")?
+            .extend("failed to read photo")?
+        };
+        let variants = variants
+            .ensure_with(ErrorCode::InvalidInput, || {
+                let size = MAX_PHOTO_SIZE / 1024 / 1024;
+                format!("photo must be at most {} MB", size)
+            })?
+            .map_err(|error| {
+                debug!(target: "lattice-api::graph", "invalid photo: {:#}", error);
+                field_error(ErrorCode::InvalidInput, "invalid photo")
+            })?;
+
+        // Store the variants.
+        let blobs = ctx
+            .entity()
+            .blobs()
+            .ensure(ErrorCode::Internal, "photo uploads are unavailable")?;
+        for (size, blob) in variants {
+            let key = photo_key(&user_id, size);
+            blobs
+                .put(&key, blob)
+                .await
+                .extend("failed to store photo")?;
+        }
+
+        // Point the user's photo URL at the new photo, with a version to
+        // bust caches of the previous one.
+        let photo_url = {
+            let urls = ctx.data::<BlobUrls>()?;
+            let key = photo_key(&user_id, DEFAULT_PHOTO_SIZE);
+            format!("{}?v={}", urls.url(&key), Utc::now().timestamp())
+        };
+        let mut user = User {
+            photo_url: Some(photo_url),
+            ..viewer
+        };
+        user.save(ctx.entity())
+            .await
+            .extend("failed to save user")?;
+
+        let user = UserObject::from(user);
+        let payload = UploadProfilePhotoPayload { user };
+        Ok(payload)
+    }
 }
 
 #[derive(Debug, Clone, InputObject)]
@@ -224,24 +549,46 @@ struct RegisterUserInput {
     last_name: String,
     phone: Option<String>,
     photo_url: Option<String>,
+
+    /// The token of an invitation to accept.
+    invitation_token: Option<String>,
 }
 
 #[derive(Debug, Clone, SimpleObject)]
 struct RegisterUserPayload {
     user: UserObject,
     is_new_user: bool,
+
+    /// The membership created by accepting an invitation.
+    membership: Option<MembershipObject>,
 }
 
 #[derive(Debug, Clone, InputObject)]
 struct UpdateUserInput {
     user_id: NodeId,
+    preferred_name: Option<String>,
+    pronouns: Option<String>,
     website_url: Option<String>,
     twitter_handle: Option<String>,
     instagram_handle: Option<String>,
     bio: Option<String>,
+    program: Option<String>,
+    year: Option<u8>,
+    graduation_term: Option<TermNameInput>,
 }
 
 #[derive(Debug, Clone, SimpleObject)]
 struct UpdateUserPayload {
     user: UserObject,
 }
+
+#[derive(InputObject)]
+struct UploadProfilePhotoInput {
+    user_id: NodeId,
+    photo: Upload,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+struct UploadProfilePhotoPayload {
+    user: UserObject,
+}
@@ -3,33 +3,245 @@ use super::prelude::*;
 pub fn with_identity<'a>(
     ctx: &'a Context<'_>,
 ) -> FieldResult<&'a IdentityClaims> {
-    let identity: &Identity = ctx.data_opt().ensure("not authorized")?;
-    let claims = identity.claims();
-    Ok(claims)
+    let identity: &Identity = ctx
+        .data_opt()
+        .ensure(ErrorCode::NotAuthenticated, "not authenticated")?;
+    match identity {
+        Identity::User(data) => Ok(&data.claims),
+        Identity::Service(_) => Err(field_error(
+            ErrorCode::Forbidden,
+            "not available to service accounts",
+        )),
+        Identity::Impersonation(_) => Err(field_error(
+            ErrorCode::Forbidden,
+            "not available while impersonating",
+        )),
+    }
 }
 
+/// Returns the viewer, who is the impersonated user if an admin is
+/// impersonating someone.
 pub async fn with_viewer<'a>(ctx: &'a Context<'_>) -> FieldResult<User> {
-    let IdentityClaims { email, .. } = with_identity(ctx)?;
-    let user = User::find_by_email(email)
+    let identity: Option<&Identity> = ctx.data_opt();
+    if let Some(impersonation) = identity.and_then(Identity::impersonation) {
+        return Ok(impersonation.target.clone());
+    }
+
+    let claims = with_identity(ctx)?;
+    let user = load_viewer(ctx, claims).await?;
+    user.ensure(ErrorCode::Forbidden, "user not registered")
+}
+
+/// Loads the user linked to the identity in `claims`.
+///
+/// Users who haven't linked the identity (i.e. those who registered before
+/// identities were linked, or with another provider) aren't found; they can
+/// link it with `registerUser` or `linkIdentity`.
+pub async fn load_viewer(
+    ctx: &Context<'_>,
+    claims: &IdentityClaims,
+) -> FieldResult<Option<User>> {
+    let IdentityClaims { iss, sub, .. } = claims;
+    let link = IdentityLink::find_by_subject(iss, sub)
+        .load(ctx.entity())
+        .await
+        .extend("failed to load identity link")?;
+    let link = match link {
+        Some(link) => link,
+        None => return Ok(None),
+    };
+    let user = link
+        .user()
         .load(ctx.entity())
         .await
         .extend("failed to load user")?;
-    match user {
-        Some(user) => Ok(user),
-        None => {
-            let error = format_err!("user not registered");
-            Err(error.into())
-        }
+    Ok(user)
+}
+
+/// Links the identity in `claims` to `user`.
+///
+/// Succeeds if the identity was linked to `user` concurrently, i.e. by
+/// simultaneous first requests.
+pub async fn link_identity(
+    ctx: &Context<'_>,
+    user: &User,
+    claims: &IdentityClaims,
+) -> FieldResult<IdentityLink> {
+    let IdentityClaims {
+        iss, sub, email, ..
+    } = claims;
+    let mut link = IdentityLink::builder()
+        .user(user.object_ref())
+        .provider(iss)
+        .subject(sub)
+        .email(email)
+        .build();
+    if let Err(error) = link.save(ctx.entity()).await {
+        let existing = IdentityLink::find_by_subject(iss, sub)
+            .load(ctx.entity())
+            .await
+            .extend("failed to load identity link")?;
+        return match existing {
+            Some(existing) if existing.user.id == user.id => Ok(existing),
+            _ => Err(error).extend("failed to save identity link"),
+        };
+    }
+    Ok(link)
+}
+
+/// Returns whether the requester is a service account that was granted
+/// `scope`.
+///
+/// Fails if the requester is a service account without `scope`, so callers
+/// only need to handle other requesters (i.e. by checking the viewer).
+pub fn with_service_scope(
+    ctx: &Context<'_>,
+    scope: Scope,
+) -> FieldResult<bool> {
+    let identity: Option<&Identity> = ctx.data_opt();
+    let service = match identity.and_then(Identity::service) {
+        Some(service) => service,
+        None => return Ok(false),
+    };
+    if !service.key.has_scope(scope) {
+        let message = format!("missing scope: {}", scope);
+        return Err(field_error(ErrorCode::Forbidden, message));
+    }
+    Ok(true)
+}
+
+/// Checks that the requester has a member account, or is a service account
+/// that was granted `scope`.
+pub async fn authorize_member(
+    ctx: &Context<'_>,
+    scope: Scope,
+) -> FieldResult<()> {
+    if !with_service_scope(ctx, scope)? {
+        with_member_account(ctx).await?;
+    }
+    Ok(())
+}
+
+/// Checks that the requester is an admin, or is a service account that was
+/// granted `scope`.
+pub async fn authorize_admin(
+    ctx: &Context<'_>,
+    scope: Scope,
+) -> FieldResult<()> {
+    if !with_service_scope(ctx, scope)? {
+        with_admin(ctx).await?;
+    }
+    Ok(())
+}
+
+/// Loads a user that the requester may modify: either the viewer, or any
+/// user if the requester is a service account with `Scope::UsersWrite`.
+pub async fn with_writable_user(
+    ctx: &Context<'_>,
+    user_id: NodeId,
+) -> FieldResult<User> {
+    let user_id = user_id
+        .get::<User>()
+        .ensure(ErrorCode::InvalidInput, "invalid user ID")?;
+    if with_service_scope(ctx, Scope::UsersWrite)? {
+        let user = User::find(&user_id)
+            .load(ctx.entity())
+            .await
+            .extend("failed to load user")?
+            .ensure(ErrorCode::NotFound, "user not found")?;
+        return Ok(user);
+    }
+
+    let viewer = with_viewer(ctx).await?;
+    if viewer.id != user_id {
+        let error = field_error(ErrorCode::Forbidden, "not authorized");
+        return Err(error);
+    }
+    Ok(viewer)
+}
+
+/// Returns the viewer if they have a member account, for resolvers that
+/// shouldn't be available to partner contacts or alumni.
+pub async fn with_member_account(ctx: &Context<'_>) -> FieldResult<User> {
+    let viewer = with_viewer(ctx).await?;
+    if !viewer.account_type.is_member() {
+        let error = field_error(
+            ErrorCode::Forbidden,
+            "not available to non-member accounts",
+        );
+        return Err(error);
+    }
+    Ok(viewer)
+}
+
+/// Returns the viewer if they are an admin, for resolvers that manage
+/// privileged resources.
+pub async fn with_admin(ctx: &Context<'_>) -> FieldResult<User> {
+    let viewer = with_viewer(ctx).await?;
+    if !viewer.is_admin {
+        let error =
+            field_error(ErrorCode::Forbidden, "not available to non-admins");
+        return Err(error);
     }
+    Ok(viewer)
+}
+
+/// Defines a GraphQL object that wraps a kernel entity.
+///
+/// The wrapper exposes the entity's `id`, its timestamps, and each listed
+/// field (converted from the entity's field with `Into`). Resolvers with
+/// logic belong in a `#[ComplexObject]` impl on the wrapper, which requires
+/// `#[graphql(complex)]`.
+macro_rules! entity_object {
+    (
+        $(#[$attr:meta])*
+        $vis:vis struct $name:ident($entity:ty) {
+            $($field:ident: $ty:ty),* $(,)?
+        }
+    ) => {
+        #[derive(Debug, Clone, SimpleObject)]
+        $(#[$attr])*
+        $vis struct $name {
+            #[graphql(skip)]
+            entity: $entity,
+            id: NodeId,
+            created_at: DateTimeScalar,
+            updated_at: DateTimeScalar,
+            $($field: $ty,)*
+        }
+
+        impl From<$entity> for $name {
+            fn from(entity: $entity) -> Self {
+                Self {
+                    id: entity.global_id().into(),
+                    created_at: entity.created_at.clone().into(),
+                    updated_at: entity.updated_at.clone().into(),
+                    $($field: entity.$field.clone().into(),)*
+                    entity,
+                }
+            }
+        }
+
+        impl std::ops::Deref for $name {
+            type Target = $entity;
+
+            fn deref(&self) -> &Self::Target {
+                &self.entity
+            }
+        }
+    };
 }
 
+pub(crate) use entity_object;
+
 pub trait ContextExt {
     fn entity(&self) -> &EntityContext;
 }
 
 impl<'a> ContextExt for Context<'a> {
     fn entity(&self) -> &EntityContext {
-        self.data_unchecked()
+        let context: &Arc<EntityContext> = self.data_unchecked();
+        context
     }
 }
 
@@ -69,40 +281,62 @@ where
 }
 
 pub trait OptionExt<T> {
-    fn ensure<C>(self, context: C) -> FieldResult<T>
+    fn ensure<M>(self, code: ErrorCode, message: M) -> FieldResult<T>
     where
-        C: Display + Send + Sync + 'static;
+        M: Display;
 
-    fn ensure_with<C, F>(self, f: F) -> FieldResult<T>
+    fn ensure_with<M, F>(self, code: ErrorCode, f: F) -> FieldResult<T>
     where
-        C: Display + Send + Sync + 'static,
-        F: FnOnce() -> C;
+        M: Display,
+        F: FnOnce() -> M;
 }
 
 impl<T> OptionExt<T> for Option<T> {
-    fn ensure<C>(self, context: C) -> FieldResult<T>
+    fn ensure<M>(self, code: ErrorCode, message: M) -> FieldResult<T>
     where
-        C: Display + Send + Sync + 'static,
+        M: Display,
     {
-        let result = self.context(context);
-        let result = Result::from(result);
-        into_field_result(result)
+        self.ok_or_else(|| field_error(code, message))
     }
 
-    fn ensure_with<C, F>(self, f: F) -> FieldResult<T>
+    fn ensure_with<M, F>(self, code: ErrorCode, f: F) -> FieldResult<T>
     where
-        C: Display + Send + Sync + 'static,
-        F: FnOnce() -> C,
+        M: Display,
+        F: FnOnce() -> M,
     {
-        let result = self.with_context(f);
-        let result = Result::from(result);
-        into_field_result(result)
+        self.ok_or_else(|| field_error(code, f()))
     }
 }
 
+/// Builds a client-facing error with the given code.
+pub fn field_error(code: ErrorCode, message: impl Display) -> FieldError {
+    let message = message.to_string();
+    FieldError::new(message)
+        .extend_with(|_, ext| ext.set("code", code.as_str()))
+}
+
+// Kernel errors that are safe to show to clients keep their message; all
+// other errors are logged and redacted.
 fn into_field_result<T>(result: Result<T>) -> FieldResult<T> {
     result.map_err(|error| {
-        let message = format!("{:#}", error);
-        FieldError::new(message)
+        if let Some(error) = error.downcast_ref::<EntityError>() {
+            use EntityError::*;
+            let code = match error {
+                NotFound(_) => Some(ErrorCode::NotFound),
+                Conflict(_) => Some(ErrorCode::Conflict),
+                Validation(_) => Some(ErrorCode::InvalidInput),
+                _ => None,
+            };
+            if let Some(code) = code {
+                return field_error(code, error);
+            }
+        }
+
+        let correlation_id = report_error(&error);
+        let message = "internal error";
+        FieldError::new(message).extend_with(|_, ext| {
+            ext.set("code", ErrorCode::Internal.as_str());
+            ext.set("correlationId", correlation_id);
+        })
     })
 }
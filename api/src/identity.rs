@@ -1,14 +1,28 @@
 use super::prelude::*;
 
 use jwt::decode as decode_token;
+use jwt::encode as encode_token;
 use jwt::Algorithm as JwtAlgorithm;
 use jwt::Validation as TokenValidation;
-use jwt::{decode_header, DecodingKey, Header, TokenData};
+use jwt::{decode_header, DecodingKey, EncodingKey, Header, TokenData};
+
+use lattice::entities::Context as EntityContext;
+use lattice::entities::{ApiKey, ServiceAccount};
+use lattice::entities::{Entity, IdentityLink, Impersonation, User};
+
+use crate::graph::NodeId;
 
 use cache_control::CacheControl;
 use http::header::CACHE_CONTROL;
 use request::Client;
-use tokio::sync::Mutex;
+use serde_json::Value as JsonValue;
+use std::cmp::{max, min};
+use std::path::PathBuf;
+use std::sync::Weak;
+use tokio::fs::read as read_file;
+use tokio::spawn as spawn_task;
+use tokio::sync::{Mutex, RwLock};
+use tokio::time::sleep;
 
 lazy_static! {
     static ref EXPIRY_LEEWAY: Duration = Duration::seconds(30);
@@ -27,20 +41,55 @@ pub struct Claims {
 }
 
 #[derive(Debug)]
-pub struct Identity(TokenData<Claims>);
+pub enum Identity {
+    /// A person, identified by a token from an identity provider.
+    User(TokenData<Claims>),
+
+    /// A service account, identified by one of its API keys.
+    Service(ServiceIdentity),
+
+    /// An admin acting as another user.
+    Impersonation(Box<ImpersonationIdentity>),
+}
+
+#[derive(Debug, Clone)]
+pub struct ServiceIdentity {
+    pub account: ServiceAccount,
+    pub key: ApiKey,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImpersonationIdentity {
+    pub target: User,
+    pub session: Impersonation,
+}
 
 impl Identity {
     pub fn new(data: TokenData<Claims>) -> Self {
-        Self(data)
+        Self::User(data)
     }
 
-    #[allow(dead_code)]
-    pub fn header(&self) -> &Header {
-        &self.0.header
+    pub fn claims(&self) -> Option<&Claims> {
+        match self {
+            Identity::User(data) => Some(&data.claims),
+            _ => None,
+        }
     }
 
-    pub fn claims(&self) -> &Claims {
-        &self.0.claims
+    pub fn service(&self) -> Option<&ServiceIdentity> {
+        match self {
+            Identity::Service(service) => Some(service),
+            _ => None,
+        }
+    }
+
+    pub fn impersonation(&self) -> Option<&ImpersonationIdentity> {
+        match self {
+            Identity::Impersonation(impersonation) => {
+                Some(impersonation.as_ref())
+            }
+            _ => None,
+        }
     }
 }
 
@@ -50,27 +99,146 @@ impl From<TokenData<Claims>> for Identity {
     }
 }
 
+impl From<ServiceIdentity> for Identity {
+    fn from(service: ServiceIdentity) -> Self {
+        Self::Service(service)
+    }
+}
+
+impl From<ImpersonationIdentity> for Identity {
+    fn from(impersonation: ImpersonationIdentity) -> Self {
+        Self::Impersonation(Box::new(impersonation))
+    }
+}
+
 #[async_trait]
 pub trait Identifier: Sync + Send {
     async fn identify(&self, token: &str) -> Result<Identity>;
 }
 
+/// Identifies service accounts by their API keys.
+pub struct ApiKeyIdentifier {
+    context: Arc<EntityContext>,
+}
+
+impl ApiKeyIdentifier {
+    pub fn new(context: Arc<EntityContext>) -> Self {
+        Self { context }
+    }
+}
+
+#[async_trait]
+#[inherent(pub)]
+impl Identifier for ApiKeyIdentifier {
+    async fn identify(&self, token: &str) -> Result<Identity> {
+        let ctx = self.context.as_ref();
+        let mut key = ApiKey::find_by_key(token)
+            .load(ctx)
+            .await
+            .context("failed to load API key")?
+            .context("unknown API key")?;
+        if !key.is_active() {
+            bail!("API key revoked or expired");
+        }
+        let account = key
+            .account()
+            .load(ctx)
+            .await
+            .context("failed to load service account")?
+            .context("missing service account")?;
+        key.touch(ctx)
+            .await
+            .context("failed to record API key use")?;
+
+        let service = ServiceIdentity { account, key };
+        Ok(service.into())
+    }
+}
+
+/// Lets admins make requests as other users, recording each session.
+pub struct Impersonator {
+    context: Arc<EntityContext>,
+}
+
+impl Impersonator {
+    /// The header that names the user to impersonate, by their node ID.
+    pub const HEADER: &'static str = "x-lattice-impersonate";
+
+    pub fn new(context: Arc<EntityContext>) -> Self {
+        Self { context }
+    }
+
+    /// Returns an identity in which the admin identified by `identity` acts
+    /// as the user with the node ID `target_id`.
+    pub async fn impersonate(
+        &self,
+        identity: &Identity,
+        target_id: &str,
+    ) -> Result<Identity> {
+        let ctx = self.context.as_ref();
+        let Claims { iss, sub, .. } = identity
+            .claims()
+            .context("only users may impersonate other users")?;
+        let admin = IdentityLink::find_by_subject(iss, sub)
+            .load(ctx)
+            .await
+            .context("failed to load identity link")?
+            .context("user not registered")?
+            .user()
+            .load(ctx)
+            .await
+            .context("failed to load user")?
+            .context("missing user")?;
+        if !admin.is_admin {
+            bail!("only admins may impersonate other users");
+        }
+
+        let target_id: NodeId =
+            serde_json::from_value(JsonValue::String(target_id.to_owned()))
+                .context("invalid user ID")?;
+        let target_id = target_id.get::<User>().context("invalid user ID")?;
+        let target = User::find(&target_id)
+            .load(ctx)
+            .await
+            .context("failed to load user")?
+            .context("user not found")?;
+
+        let session = Impersonation::record(ctx, &admin, &target)
+            .await
+            .context("failed to record impersonation")?;
+        info!(
+            target: "api::identity",
+            admin = %admin.id,
+            target = %target.id,
+            session = %session.id,
+            "impersonating user"
+        );
+
+        let impersonation = ImpersonationIdentity { target, session };
+        Ok(impersonation.into())
+    }
+}
+
 const FIREBASE_KEY_URL: &str = "https://www.googleapis.com/service_accounts/v1/jwk/securetoken@system.gserviceaccount.com";
 const FIREBASE_ISS_URL: &str = "https://securetoken.google.com";
 
 #[derive(Debug)]
 pub struct FirebaseIdentifier {
     project_id: String,
-    client: Mutex<FirebaseClient>,
+    client: Arc<JwksClient>,
 }
 
 impl FirebaseIdentifier {
+    /// Creates an identifier, and starts refreshing its keys in the
+    /// background.
+    ///
+    /// Must be called from within a Tokio runtime.
     pub fn new(project_id: impl Into<String>) -> Self {
-        let project_id = project_id.into();
-        let client = FirebaseClient::new(project_id.clone());
+        let source = JwksSource::Uri(FIREBASE_KEY_URL.to_owned());
+        let client = JwksClient::start(source);
         Self {
-            project_id,
-            client: Mutex::new(client),
+            project_id: project_id.into(),
+            client,
         }
     }
 }
@@ -95,17 +263,13 @@ impl Identifier for FirebaseIdentifier {
         validation.set_audience(&[self.expected_aud()]);
 
         let header = decode_header(token).context("failed to decode header")?;
-        let kid = header.kid.context("missing key ID")?;
-
-        let keys = {
-            let mut client = self.client.lock().await;
-            client
-                .keys()
-                .await
-                .context("failed to load decoding keys")?
-        };
-        let key = keys.get(&kid).context("no matching decoding keys")?;
-        let data = decode_token::<Claims>(token, key, &validation)?;
+        let key = self
+            .client
+            .key(header.kid.as_deref())
+            .await
+            .context("failed to load decoding key")?
+            .context("no matching decoding keys")?;
+        let data = decode_token::<Claims>(token, &key, &validation)?;
 
         let expires_at = Utc::now() + *EXPIRY_LEEWAY;
         let issued_at = Utc.timestamp(
@@ -123,74 +287,528 @@ impl Identifier for FirebaseIdentifier {
     }
 }
 
+const LOCAL_ISSUER: &str = "lattice-local";
+const LOCAL_AUDIENCE: &str = "lattice";
+
+/// Identifies users by tokens signed with a local secret, so that the API can
+/// be used offline during development.
+///
+/// Tokens can be minted for any email, so this must never be used in
+/// production.
+#[derive(Debug)]
+pub struct LocalIdentifier {
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey<'static>,
+}
+
+impl LocalIdentifier {
+    /// How long minted tokens are valid for, in seconds.
+    pub const TOKEN_TTL: i64 = 24 * 60 * 60;
+
+    pub fn new(secret: &[u8]) -> Self {
+        Self {
+            encoding_key: EncodingKey::from_secret(secret),
+            decoding_key: DecodingKey::from_secret(secret).into_static(),
+        }
+    }
+
+    /// Mints a token that identifies the user with the given email.
+    pub fn mint(&self, email: &str) -> Result<String> {
+        let email = email.trim().to_lowercase();
+        if !email.contains('@') {
+            bail!("invalid email: {}", email);
+        }
+        let now = Utc::now();
+        let expires_at = now + Duration::seconds(Self::TOKEN_TTL);
+        let sub = format!("local:{}", &email);
+        let claims = Claims {
+            exp: expires_at.timestamp().try_into()?,
+            iat: now.timestamp().try_into()?,
+            aud: LOCAL_AUDIENCE.to_owned(),
+            iss: LOCAL_ISSUER.to_owned(),
+            user_id: sub.clone(),
+            sub,
+            email,
+            email_verified: true,
+        };
+        let header = Header::new(JwtAlgorithm::HS256);
+        let token = encode_token(&header, &claims, &self.encoding_key)
+            .context("failed to encode token")?;
+        Ok(token)
+    }
+}
+
+#[async_trait]
+#[inherent(pub)]
+impl Identifier for LocalIdentifier {
+    async fn identify(&self, token: &str) -> Result<Identity> {
+        let mut validation = TokenValidation::new(JwtAlgorithm::HS256);
+        validation.leeway = EXPIRY_LEEWAY.num_seconds().try_into().unwrap();
+        validation.iss = Some(LOCAL_ISSUER.to_owned());
+        validation.set_audience(&[LOCAL_AUDIENCE]);
+
+        let data =
+            decode_token::<Claims>(token, &self.decoding_key, &validation)?;
+        Ok(data.into())
+    }
+}
+
+/// Where a JSON Web Key Set is loaded from.
+#[derive(Debug, Clone)]
+pub enum JwksSource {
+    Uri(String),
+    File(PathBuf),
+}
+
+/// The names of the token claims that identity claims are read from.
+#[derive(Debug, Clone)]
+pub struct ClaimMapping {
+    pub subject: String,
+    pub email: String,
+    pub email_verified: String,
+}
+
+impl Default for ClaimMapping {
+    fn default() -> Self {
+        Self {
+            subject: "sub".to_owned(),
+            email: "email".to_owned(),
+            email_verified: "email_verified".to_owned(),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
-struct FirebaseClient {
+pub struct OidcConfig {
+    pub issuer: String,
+    pub audience: String,
+    pub jwks: JwksSource,
+    pub claims: ClaimMapping,
+}
+
+/// Identifies users by tokens from a generic OpenID Connect provider.
+#[derive(Debug)]
+pub struct OidcIdentifier {
+    issuer: String,
+    audience: String,
+    claims: ClaimMapping,
+    client: Arc<JwksClient>,
+}
+
+impl OidcIdentifier {
+    /// Creates an identifier, and starts loading its keys in the background.
+    ///
+    /// Must be called from within a Tokio runtime.
+    pub fn new(config: OidcConfig) -> Self {
+        let OidcConfig {
+            issuer,
+            audience,
+            jwks,
+            claims,
+        } = config;
+        Self {
+            issuer,
+            audience,
+            claims,
+            client: JwksClient::start(jwks),
+        }
+    }
+}
+
+impl OidcIdentifier {
+    fn claim<'a>(
+        claims: &'a Map<String, JsonValue>,
+        name: &str,
+    ) -> Result<&'a JsonValue> {
+        claims
+            .get(name)
+            .with_context(|| format!("missing claim `{}`", name))
+    }
+
+    fn string_claim(
+        claims: &Map<String, JsonValue>,
+        name: &str,
+    ) -> Result<String> {
+        let value = Self::claim(claims, name)?;
+        let value = value
+            .as_str()
+            .with_context(|| format!("claim `{}` is not a string", name))?;
+        Ok(value.to_owned())
+    }
+
+    fn timestamp_claim(
+        claims: &Map<String, JsonValue>,
+        name: &str,
+    ) -> Result<u64> {
+        let value = Self::claim(claims, name)?;
+        value
+            .as_u64()
+            .with_context(|| format!("claim `{}` is not a timestamp", name))
+    }
+}
+
+#[async_trait]
+#[inherent(pub)]
+impl Identifier for OidcIdentifier {
+    async fn identify(&self, token: &str) -> Result<Identity> {
+        let mut validation = TokenValidation {
+            algorithms: vec![
+                JwtAlgorithm::RS256,
+                JwtAlgorithm::RS384,
+                JwtAlgorithm::RS512,
+            ],
+            ..TokenValidation::default()
+        };
+        validation.leeway = EXPIRY_LEEWAY.num_seconds().try_into().unwrap();
+        validation.iss = Some(self.issuer.clone());
+        validation.set_audience(&[&self.audience]);
+
+        let header = decode_header(token).context("failed to decode header")?;
+        let key = self
+            .client
+            .key(header.kid.as_deref())
+            .await
+            .context("failed to load decoding key")?
+            .context("no matching decoding keys")?;
+        let TokenData { header, claims } =
+            decode_token::<Map<String, JsonValue>>(token, &key, &validation)?;
+
+        // Map the provider's claims onto our own.
+        let ClaimMapping {
+            subject,
+            email,
+            email_verified,
+        } = &self.claims;
+        let sub = Self::string_claim(&claims, subject)?;
+        let email_verified = match claims.get(email_verified) {
+            Some(value) => value.as_bool().with_context(|| {
+                format!("claim `{}` is not a boolean", email_verified)
+            })?,
+            None => false,
+        };
+        let claims = Claims {
+            exp: Self::timestamp_claim(&claims, "exp")?,
+            iat: Self::timestamp_claim(&claims, "iat")?,
+            aud: self.audience.clone(),
+            iss: self.issuer.clone(),
+            user_id: sub.clone(),
+            sub,
+            email: Self::string_claim(&claims, email)?,
+            email_verified,
+        };
+
+        let expires_at = Utc::now() + *EXPIRY_LEEWAY;
+        let issued_at = Utc.timestamp(
+            claims
+                .iat
+                .try_into()
+                .context("failed to convert issued-at time to u64")?,
+            0,
+        );
+        if issued_at > expires_at {
+            bail!("invalid issued-at time");
+        }
+
+        let data = TokenData { header, claims };
+        Ok(data.into())
+    }
+}
+
+/// Loads and caches keys from a `JwksSource`.
+///
+/// Keys from a URI are refreshed in the background ahead of their expiry,
+/// according to the response's `max-age` (or `DEFAULT_JWKS_MAX_AGE`, if it
+/// has none), retrying with backoff on failure; until a refresh succeeds, the
+/// last good keys continue to be served. Keys from a file are loaded once. A
+/// token with an unknown key ID forces a refresh, in case the keys were
+/// rotated early.
+///
+/// Lookups of known keys only hold a lock long enough to read the current
+/// keys, never while keys are being fetched; lookups of unknown keys wait for
+/// a refresh in progress to finish.
+#[derive(Debug)]
+struct JwksClient {
     client: Client,
-    project_id: String,
-    refresh_at: DateTime,
+    source: JwksSource,
+    keys: RwLock<Arc<JwksKeys>>,
+
+    /// Serializes refreshes, and holds the time of the last attempt.
+    refresh: Mutex<Option<DateTime>>,
+}
+
+#[derive(Debug, Default)]
+struct JwksKeys {
     keys: Map<String, DecodingKey<'static>>,
+
+    /// The only key, if there is exactly one, for tokens without a key ID.
+    only: Option<DecodingKey<'static>>,
+
+    fetched_at: Option<DateTime>,
+    refresh_at: Option<DateTime>,
 }
 
-impl FirebaseClient {
-    pub fn new(project_id: String) -> Self {
-        Self {
+impl JwksKeys {
+    fn get(&self, kid: Option<&str>) -> Option<DecodingKey<'static>> {
+        match kid {
+            Some(kid) => self.keys.get(kid).cloned(),
+            None => self.only.clone(),
+        }
+    }
+}
+
+const DEFAULT_JWKS_MAX_AGE: i64 = 60 * 60;
+
+impl JwksClient {
+    /// How long before keys expire to refresh them, in seconds. Keys that
+    /// expire sooner are refreshed halfway through their lifetime, and never
+    /// sooner than `MIN_REFRESH_INTERVAL` after they were fetched.
+    const REFRESH_MARGIN: i64 = 5 * 60;
+    const MIN_REFRESH_INTERVAL: i64 = 30;
+
+    /// How long to wait before retrying a failed refresh, in seconds. The
+    /// delay doubles on each failure, up to `MAX_RETRY_DELAY`.
+    const MIN_RETRY_DELAY: i64 = 1;
+    const MAX_RETRY_DELAY: i64 = 5 * 60;
+
+    /// How often an unknown key ID may force a refresh, in seconds.
+    const MIN_FORCED_REFRESH_INTERVAL: i64 = 30;
+
+    const FETCH_TIMEOUT: StdDuration = StdDuration::from_secs(10);
+
+    /// Creates a client, and starts loading its keys in the background.
+    ///
+    /// Must be called from within a Tokio runtime.
+    pub fn start(source: JwksSource) -> Arc<Self> {
+        let client = Arc::new(Self {
             client: Client::new(),
-            project_id,
-            refresh_at: Utc::now(),
+            source,
             keys: Default::default(),
+            refresh: Default::default(),
+        });
+        spawn_task(Self::run(Arc::downgrade(&client)));
+        client
+    }
+
+    /// Returns the key with the given ID (or the only key, if no ID is
+    /// given), forcing a refresh if it isn't known.
+    pub async fn key(
+        &self,
+        kid: Option<&str>,
+    ) -> Result<Option<DecodingKey<'static>>> {
+        let keys = self.current().await;
+        if let Some(key) = keys.get(kid) {
+            return Ok(Some(key));
         }
+
+        self.force_refresh(keys.fetched_at).await?;
+        let keys = self.current().await;
+        Ok(keys.get(kid))
     }
 
-    pub async fn keys(&mut self) -> Result<Map<String, DecodingKey<'static>>> {
-        self.sync().await?;
-        Ok(self.keys.clone())
+    /// Refreshes keys ahead of their expiry, until the client is dropped (or
+    /// until keys are loaded, if they're from a file).
+    async fn run(client: Weak<Self>) {
+        let mut retry_delay: Option<Duration> = None;
+        loop {
+            let delay = match retry_delay {
+                Some(delay) => delay,
+                None => match client.upgrade() {
+                    Some(client) => client.refresh_delay().await,
+                    None => return,
+                },
+            };
+            sleep(delay.to_std().unwrap_or_default()).await;
+
+            let client = match client.upgrade() {
+                Some(client) => client,
+                None => return,
+            };
+            match client.refresh().await {
+                Ok(()) if matches!(client.source, JwksSource::File(_)) => {
+                    return
+                }
+                Ok(()) => retry_delay = None,
+                Err(error) => {
+                    let delay = match retry_delay {
+                        Some(delay) => min(
+                            delay * 2,
+                            Duration::seconds(Self::MAX_RETRY_DELAY),
+                        ),
+                        None => Duration::seconds(Self::MIN_RETRY_DELAY),
+                    };
+                    warn!(
+                        target: "api::identity",
+                        retry_in = delay.num_seconds(),
+                        "failed to refresh signing keys: {:#}",
+                        error
+                    );
+                    retry_delay = Some(delay);
+                }
+            }
+        }
     }
 }
 
-impl FirebaseClient {
-    async fn sync(&mut self) -> Result<()> {
-        let refresh_at = self.refresh_at - Duration::seconds(1);
-        if Utc::now() <= refresh_at {
-            return Ok(());
-        }
-        let response = self.client.get(FIREBASE_KEY_URL).send().await?;
+impl JwksClient {
+    async fn current(&self) -> Arc<JwksKeys> {
+        let keys = self.keys.read().await;
+        keys.clone()
+    }
 
-        let cache_control = response
-            .headers()
-            .get(CACHE_CONTROL)
-            .context("missing cache-control header")?;
-        let cache_control = cache_control
-            .to_str()
-            .context("failed to parse cache-control header")?;
-        let cache_control = CacheControl::from_value(cache_control)
-            .context("missing cache-control directives")?;
+    async fn refresh_delay(&self) -> Duration {
+        let keys = self.current().await;
+        match keys.refresh_at {
+            Some(refresh_at) => max(refresh_at - Utc::now(), Duration::zero()),
+            None => Duration::zero(),
+        }
+    }
 
-        let max_age = cache_control.max_age.context("missing max-age")?;
-        self.refresh_at = Utc::now() + max_age;
+    async fn refresh(&self) -> Result<()> {
+        let mut attempted_at = self.refresh.lock().await;
+        *attempted_at = Some(Utc::now());
+        self.sync().await
+    }
 
-        let data = response
-            .json::<JwkData>()
-            .await
-            .context("failed to parse response")?;
-        let mut keys = Map::<String, DecodingKey<'static>>::new();
-        for jwk in data.keys {
-            let JwkInfo { kid, n, e } = jwk;
-            let key = DecodingKey::from_rsa_components(&n, &e).into_static();
-            keys.insert(kid, key);
+    /// Refreshes keys, unless they were refreshed since `fetched_at` or a
+    /// refresh was attempted too recently.
+    ///
+    /// Waits for a refresh in progress (i.e. the initial load) to finish
+    /// first, so that its keys are seen.
+    async fn force_refresh(&self, fetched_at: Option<DateTime>) -> Result<()> {
+        let mut attempted_at = self.refresh.lock().await;
+        if self.current().await.fetched_at != fetched_at {
+            return Ok(());
+        }
+        if let Some(attempted_at) = *attempted_at {
+            let interval = Duration::seconds(Self::MIN_FORCED_REFRESH_INTERVAL);
+            if Utc::now() - attempted_at < interval {
+                return Ok(());
+            }
         }
-        self.keys = keys;
+        *attempted_at = Some(Utc::now());
+        self.sync().await
+    }
+
+    async fn sync(&self) -> Result<()> {
+        let (data, max_age) = match &self.source {
+            JwksSource::Uri(uri) => {
+                let response = self
+                    .client
+                    .get(uri)
+                    .timeout(Self::FETCH_TIMEOUT)
+                    .send()
+                    .await
+                    .context("failed to request keys")?
+                    .error_for_status()
+                    .context("bad response")?;
+                let max_age = response
+                    .headers()
+                    .get(CACHE_CONTROL)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(CacheControl::from_value)
+                    .and_then(|cache_control| cache_control.max_age)
+                    .unwrap_or_else(|| Duration::seconds(DEFAULT_JWKS_MAX_AGE));
+                let data = response
+                    .json::<JwkData>()
+                    .await
+                    .context("failed to parse response")?;
+                (data, Some(max_age))
+            }
+            JwksSource::File(path) => {
+                let data = read_file(path)
+                    .await
+                    .context("failed to read JWKS file")?;
+                let data = serde_json::from_slice::<JwkData>(&data)
+                    .context("failed to parse JWKS file")?;
+                (data, None)
+            }
+        };
+
+        // Only schedule the next refresh once the keys have parsed.
+        let fetched_at = Utc::now();
+        let refresh_at = max_age.map(|max_age| {
+            let margin = Duration::seconds(Self::REFRESH_MARGIN);
+            let refresh_after = max(max_age - margin, max_age / 2);
+            let refresh_after = max(
+                refresh_after,
+                Duration::seconds(Self::MIN_REFRESH_INTERVAL),
+            );
+            fetched_at + refresh_after
+        });
+        let (keys, only) = data.decoding_keys();
+        let keys = JwksKeys {
+            keys,
+            only,
+            fetched_at: Some(fetched_at),
+            refresh_at,
+        };
+        *self.keys.write().await = Arc::new(keys);
         Ok(())
     }
 }
 
-#[derive(Debug, Clone, Hash, Serialize, Deserialize)]
+/// A JSON Web Key Set, whose keys are parsed individually so that keys we
+/// can't use (i.e. non-RSA keys) don't prevent using the rest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct JwkData {
-    keys: Vec<JwkInfo>,
+    keys: Vec<JsonValue>,
+}
+
+impl JwkData {
+    /// Returns the RSA keys by their IDs, and the only RSA key, if there is
+    /// exactly one.
+    fn decoding_keys(
+        self,
+    ) -> (
+        Map<String, DecodingKey<'static>>,
+        Option<DecodingKey<'static>>,
+    ) {
+        let mut keys = Map::<String, DecodingKey<'static>>::new();
+        let mut rsa_keys = Vec::new();
+        for jwk in self.keys {
+            let jwk = match serde_json::from_value::<JwkInfo>(jwk) {
+                Ok(jwk) => jwk,
+                Err(error) => {
+                    warn!(
+                        target: "api::identity",
+                        "skipping malformed signing key: {}", error
+                    );
+                    continue;
+                }
+            };
+            let JwkInfo { kty, kid, n, e } = jwk;
+            if kty != "RSA" {
+                continue;
+            }
+            let (n, e) = match (n, e) {
+                (Some(n), Some(e)) => (n, e),
+                _ => {
+                    warn!(
+                        target: "api::identity",
+                        kid = kid.as_deref().unwrap_or_default(),
+                        "skipping RSA signing key without modulus or exponent"
+                    );
+                    continue;
+                }
+            };
+            let key = DecodingKey::from_rsa_components(&n, &e).into_static();
+            if let Some(kid) = kid {
+                keys.insert(kid, key.clone());
+            }
+            rsa_keys.push(key);
+        }
+        let only = match rsa_keys.len() {
+            1 => rsa_keys.pop(),
+            _ => None,
+        };
+        (keys, only)
+    }
 }
 
 #[derive(Debug, Clone, Hash, Serialize, Deserialize)]
 struct JwkInfo {
-    kid: String,
-    n: String,
-    e: String,
+    kty: String,
+    kid: Option<String>,
+    n: Option<String>,
+    e: Option<String>,
 }
@@ -0,0 +1,180 @@
+use super::prelude::*;
+
+use crate::identity::Identity;
+
+use bson::oid::ObjectId;
+use lru::LruCache;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// A token-bucket rate limit, which allows bursts of up to `burst` requests,
+/// refilled at `burst` requests per `period`.
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    burst: u32,
+    period: StdDuration,
+}
+
+impl RateLimit {
+    /// Parses a limit of the form "<requests>/<seconds>" (i.e. "300/60").
+    pub fn parse(limit: &str) -> Result<Self> {
+        let mut parts = limit.splitn(2, '/');
+        let requests = parts.next().unwrap_or(limit);
+        let seconds = parts.next().context("missing period")?;
+        let burst: u32 = requests
+            .trim()
+            .parse()
+            .context("failed to parse request count")?;
+        let seconds: u64 =
+            seconds.trim().parse().context("failed to parse period")?;
+        if burst == 0 || seconds == 0 {
+            bail!("request count and period must be positive");
+        }
+        let limit = Self {
+            burst,
+            period: StdDuration::from_secs(seconds),
+        };
+        Ok(limit)
+    }
+
+    /// How many requests are refilled per second.
+    fn rate(&self) -> f64 {
+        f64::from(self.burst) / self.period.as_secs_f64()
+    }
+}
+
+/// Who a request counts against.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub enum RateLimitKey {
+    /// A user, by their identity provider and subject.
+    User { provider: String, subject: String },
+
+    /// A service account's API key, by its ID.
+    ApiKey(ObjectId),
+
+    /// An unauthenticated client, by its IP address.
+    Client(IpAddr),
+}
+
+impl RateLimitKey {
+    /// Returns the key for a request from `identity`, or from the client at
+    /// `addr` if the request is unauthenticated.
+    ///
+    /// Requests are counted before impersonation is resolved, so an admin's
+    /// impersonated requests count against the admin's own user key.
+    pub fn new(
+        identity: Option<&Identity>,
+        addr: Option<IpAddr>,
+    ) -> Option<Self> {
+        let key = match identity {
+            Some(Identity::User(data)) => RateLimitKey::User {
+                provider: data.claims.iss.clone(),
+                subject: data.claims.sub.clone(),
+            },
+            Some(Identity::Service(service)) => {
+                RateLimitKey::ApiKey(service.key.id.clone())
+            }
+            Some(Identity::Impersonation(_)) | None => {
+                RateLimitKey::Client(addr?)
+            }
+        };
+        Some(key)
+    }
+}
+
+/// Limits requests with a token bucket per key.
+#[derive(Debug)]
+pub struct RateLimiter {
+    limit: RateLimit,
+    buckets: Mutex<LruCache<RateLimitKey, Bucket>>,
+}
+
+impl RateLimiter {
+    /// How many buckets to keep before evicting the least recently used
+    /// ones.
+    const MAX_BUCKETS: usize = 10_000;
+
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            buckets: Mutex::new(LruCache::new(Self::MAX_BUCKETS)),
+        }
+    }
+
+    /// Takes a request from `key`'s bucket, or returns how long until one is
+    /// available.
+    pub fn check(&self, key: &RateLimitKey) -> Result<(), StdDuration> {
+        let now = Instant::now();
+        let burst = f64::from(self.limit.burst);
+        let rate = self.limit.rate();
+
+        let mut buckets = self.buckets.lock().unwrap();
+        let mut bucket = buckets.get(key).copied().unwrap_or(Bucket {
+            tokens: burst,
+            refilled_at: now,
+        });
+        bucket.refill(now, burst, rate);
+        let result = if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            let wait = (1.0 - bucket.tokens) / rate;
+            Err(StdDuration::from_secs_f64(wait))
+        };
+        buckets.put(key.clone(), bucket);
+        result
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl Bucket {
+    fn refill(&mut self, now: Instant, burst: f64, rate: f64) {
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate).min(burst);
+        self.refilled_at = now;
+    }
+}
+
+/// A rate limiter for all requests by client, which is checked before
+/// requests are identified, and separate rate limiters for GraphQL queries
+/// and mutations.
+#[derive(Debug)]
+pub struct RateLimits {
+    pub clients: Option<RateLimiter>,
+    pub queries: Option<RateLimiter>,
+    pub mutations: Option<RateLimiter>,
+}
+
+impl RateLimits {
+    /// Checks any request from the client at `addr` against the client
+    /// limiter, if clients are limited.
+    pub fn check_client(&self, addr: IpAddr) -> Result<(), StdDuration> {
+        match &self.clients {
+            Some(limiter) => limiter.check(&RateLimitKey::Client(addr)),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks a request against the limiter for its kind, if it's limited.
+    pub fn check(
+        &self,
+        key: &RateLimitKey,
+        is_mutation: bool,
+    ) -> Result<(), StdDuration> {
+        let limiter = if is_mutation {
+            &self.mutations
+        } else {
+            &self.queries
+        };
+        match limiter {
+            Some(limiter) => limiter.check(key),
+            None => Ok(()),
+        }
+    }
+}
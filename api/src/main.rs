@@ -1,32 +1,44 @@
 use bson::doc;
+use http::header::RETRY_AFTER;
 use http::StatusCode;
 use mongodb::Client;
+use tokio::spawn as spawn_task;
+use tokio::time::interval;
 use tracing_subscriber::fmt::init as init_tracer;
 
 use std::borrow::Cow;
 use std::convert::Infallible;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::ops::Deref;
 use std::path::Path;
 
 use anyhow::Context as AnyhowContext;
 use anyhow::Result;
 
+use warp::addr::remote as remote_addr;
+use warp::body::json as json_body;
 use warp::header::optional as header;
 use warp::http::Response as HttpResponse;
 use warp::path::end as path_end;
 use warp::path::full as full_path;
-use warp::path::FullPath;
+use warp::path::tail as tail_path;
+use warp::path::{FullPath, Tail};
 use warp::reject::custom as custom_rejection;
+use warp::reject::not_found;
 use warp::reject::Reject;
 use warp::reply::json as reply_json;
 use warp::reply::with_status as reply_with_status;
-use warp::{any, get, path, serve};
-use warp::{Filter, Rejection};
+use warp::{any, get, path, post, serve};
+use warp::{Filter, Rejection, Reply};
 
 use graphql::http::playground_source as graphql_playground_source;
 use graphql::http::GraphQLPlaygroundConfig;
+use graphql::parser::parse_query;
+use graphql::parser::types::OperationType;
+use graphql::Error as GraphQLError;
+use graphql::ErrorExtensions;
 use graphql::Request as GraphQLRequest;
+use graphql::Response as GraphQLExecutionResponse;
 use graphql::{EmptySubscription, Schema};
 
 use graphql_warp::graphql as warp_graphql;
@@ -34,17 +46,28 @@ use graphql_warp::graphql_subscription as warp_graphql_subscription;
 use graphql_warp::BadRequest as BadGraphQLRequest;
 use graphql_warp::Response as GraphQLResponse;
 
-use lattice::entities::{BuildInfo, Context};
+use lattice::blobs::{Blob, BlobStore, FileBlobStore};
+use lattice::entities::{ApiKey, BuildInfo, CacheStats, Context, EntityCache};
 use lattice::env::load as load_env;
 use lattice::env::var as env_var;
 use lattice::env::var_or as env_var_or;
 
+mod error;
 mod graph;
 mod identity;
+mod limits;
+mod photos;
+mod policy;
 mod prelude;
 
+use error::{report_error, ErrorCode};
 use graph::{Mutation, Query};
-use identity::{FirebaseIdentifier, Identifier, Identity};
+use identity::{ApiKeyIdentifier, FirebaseIdentifier, LocalIdentifier};
+use identity::{ClaimMapping, JwksSource, OidcConfig, OidcIdentifier};
+use identity::{Identifier, Identity, Impersonator};
+use limits::{RateLimit, RateLimitKey, RateLimiter, RateLimits};
+use photos::BlobUrls;
+use policy::EmailPolicy;
 use prelude::*;
 
 #[tokio::main]
@@ -77,16 +100,144 @@ async fn main() -> Result<()> {
             .run_command(doc! { "ping": 1 }, None)
             .await
             .context("failed to connect to MongoDB")?;
-        Context::new(database)
+        let context = Context::new(database);
+
+        // Enable entity caching if a capacity is configured.
+        let cache_capacity: usize = env_var_or("CACHE_CAPACITY", "0")
+            .context("failed to get cache capacity")?
+            .parse()
+            .context("failed to parse cache capacity")?;
+        if cache_capacity > 0 {
+            let cache_ttl: u64 = env_var_or("CACHE_TTL", "60")
+                .context("failed to get cache TTL")?
+                .parse()
+                .context("failed to parse cache TTL")?;
+            let cache_ttl = StdDuration::from_secs(cache_ttl);
+            let cache = EntityCache::new(cache_capacity, cache_ttl);
+            context.with_cache(cache)
+        } else {
+            context
+        }
+    };
+
+    // Build blob store.
+    let blobs: Arc<dyn BlobStore> = {
+        let dir = env_var_or("BLOB_DIR", "./blobs")
+            .context("failed to get blob directory")?;
+        Arc::new(FileBlobStore::new(dir))
+    };
+    let context = Arc::new(context.with_blobs(blobs.clone()));
+
+    // Log entity cache stats periodically, if caching is enabled. An
+    // interval of 0 disables logging.
+    let cache_stats_interval: u64 = env_var_or("CACHE_STATS_INTERVAL", "300")
+        .context("failed to get cache stats interval")?
+        .parse()
+        .context("failed to parse cache stats interval")?;
+    if context.cache().is_some() && cache_stats_interval > 0 {
+        let context = context.clone();
+        spawn_task(async move {
+            let period = StdDuration::from_secs(cache_stats_interval);
+            let mut ticker = interval(period);
+
+            // Skip the first tick, which completes immediately.
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                if let Some(cache) = context.cache() {
+                    let CacheStats {
+                        hits,
+                        misses,
+                        entries,
+                    } = cache.stats();
+                    info!(
+                        target: "server",
+                        hits, misses, entries, "entity cache stats"
+                    );
+                }
+            }
+        });
+    }
+    let blob_urls = {
+        let public_url = env_var_or("PUBLIC_URL", "http://localhost:3000")
+            .context("failed to get public URL")?;
+        BlobUrls::new(public_url)
+    };
+
+    // Build email policy.
+    let email_policy = {
+        let domains = env_var_or("ALLOWED_DOMAINS", "uwblueprint.org")
+            .context("failed to get allowed email domains")?;
+        let emails = env_var_or("ALLOWED_EMAILS", "")
+            .context("failed to get allowed emails")?;
+        EmailPolicy::parse(&domains, &emails)
+            .context("failed to parse email policy")?
     };
 
     // Build identitifier.
-    let identifier = {
-        let project_id =
-            env_var("FIREBASE_ID").context("failed to parse firebase ID")?;
-        FirebaseIdentifier::new(project_id)
+    let mut local_identifier: Option<Arc<LocalIdentifier>> = None;
+    let identifier: Arc<dyn Identifier> = {
+        let provider = env_var_or("IDENTITY_PROVIDER", "firebase")
+            .context("failed to get identity provider")?;
+        match provider.as_str() {
+            "firebase" => {
+                let project_id = env_var("FIREBASE_ID")
+                    .context("failed to parse firebase ID")?;
+                let identifier = FirebaseIdentifier::new(project_id);
+                Arc::new(identifier)
+            }
+            "oidc" => {
+                let config = oidc_config()?;
+                let identifier = OidcIdentifier::new(config);
+                Arc::new(identifier)
+            }
+            "local" => {
+                let dev_mode = env_var_or("DEV_MODE", "false")
+                    .context("failed to get dev mode")?;
+                if dev_mode != "true" {
+                    bail!(
+                        "local identity provider requires {}=true",
+                        lattice::env::key("DEV_MODE")
+                    );
+                }
+                let secret = env_var_or("LOCAL_SECRET", "lattice")
+                    .context("failed to get local identity secret")?;
+                let identifier =
+                    Arc::new(LocalIdentifier::new(secret.as_bytes()));
+                local_identifier = Some(identifier.clone());
+                warn!(
+                    target: "server",
+                    "using local identity provider; do not use in production",
+                );
+                identifier
+            }
+            provider => bail!("unknown identity provider: {}", provider),
+        }
+    };
+
+    // Build API key identifier.
+    let api_keys = {
+        let identifier = ApiKeyIdentifier::new(context.clone());
+        Arc::new(identifier)
     };
 
+    // Build impersonator.
+    let impersonator = Arc::new(Impersonator::new(context.clone()));
+
+    // Build rate limits.
+    let rate_limits = {
+        let limits = RateLimits {
+            clients: rate_limiter("CLIENT_RATE_LIMIT", "600/60")?,
+            queries: rate_limiter("QUERY_RATE_LIMIT", "300/60")?,
+            mutations: rate_limiter("MUTATION_RATE_LIMIT", "60/60")?,
+        };
+        Arc::new(limits)
+    };
+    let trust_proxy: bool = env_var_or("TRUST_PROXY", "false")
+        .context("failed to get proxy trust setting")?
+        .parse()
+        .context("failed to parse proxy trust setting")?;
+
     // Build GraphQL schema.
     let schema = {
         let query = Query::new();
@@ -94,27 +245,85 @@ async fn main() -> Result<()> {
         let subscription = EmptySubscription;
         Schema::build(query, mutation, subscription)
             .data(build_info)
-            .data(context)
+            .data(context.clone())
+            .data(blob_urls)
+            .data(email_policy)
+            .data(identifier.clone())
             .finish()
     };
 
     // Build GraphQL filter.
     let graphql = {
-        let identifier = Arc::new(identifier);
+        let rate_limits = rate_limits.clone();
         warp_graphql(schema.clone())
             .untuple_one()
-            .and(identify(identifier))
+            .and(limit_client(rate_limits.clone(), trust_proxy))
+            .and(identify(identifier.clone(), api_keys.clone()))
+            .and(header(Impersonator::HEADER))
             .and_then(
-                |schema: Schema<_, _, _>,
-                 request: GraphQLRequest,
-                 identity: Option<Identity>| async move {
-                    let request = match identity {
-                        Some(identity) => request.data(identity),
-                        None => request,
-                    };
-                    let response = schema.execute(request).await;
-                    let response = GraphQLResponse::from(response);
-                    Ok::<_, Infallible>(response)
+                move |schema: Schema<_, _, _>,
+                      request: GraphQLRequest,
+                      addr: Option<IpAddr>,
+                      identity: Option<Identity>,
+                      impersonate: Option<String>| {
+                    let rate_limits = rate_limits.clone();
+                    let impersonator = impersonator.clone();
+                    async move {
+                        let is_mutation = has_mutation(&request);
+                        let key = RateLimitKey::new(identity.as_ref(), addr);
+                        if let Some(key) = key {
+                            rate_limits
+                                .check(&key, is_mutation)
+                                .map_err(rate_limited)?;
+                        }
+
+                        // Impersonate only after the admin's own request was
+                        // counted, since each impersonated request is
+                        // recorded.
+                        let identity = match (identity, impersonate) {
+                            (Some(identity), Some(target_id)) => {
+                                let identity = impersonator
+                                    .impersonate(&identity, &target_id)
+                                    .await
+                                    .context("failed to impersonate user")
+                                    .map_err(|error| {
+                                        custom_rejection(AuthorizationError {
+                                            error,
+                                            status_code: StatusCode::FORBIDDEN,
+                                        })
+                                    })?;
+                                Some(identity)
+                            }
+                            (identity, _) => identity,
+                        };
+
+                        // Admins may only look around while impersonating.
+                        let impersonating = identity
+                            .as_ref()
+                            .and_then(Identity::impersonation)
+                            .is_some();
+                        if impersonating && is_mutation {
+                            let error = GraphQLError::new(
+                                "mutations not allowed while impersonating",
+                            )
+                            .extend_with(|_, ext| {
+                                ext.set("code", ErrorCode::Forbidden.as_str())
+                            });
+                            let errors = vec![error.into_server_error()];
+                            let response =
+                                GraphQLExecutionResponse::from_errors(errors);
+                            let response = GraphQLResponse::from(response);
+                            return Ok::<_, Rejection>(response);
+                        }
+
+                        let request = match identity {
+                            Some(identity) => request.data(identity),
+                            None => request,
+                        };
+                        let response = schema.execute(request).await;
+                        let response = GraphQLResponse::from(response);
+                        Ok::<_, Rejection>(response)
+                    }
                 },
             )
     };
@@ -141,22 +350,127 @@ async fn main() -> Result<()> {
         .and(path_end())
         .and(graphql_subscription.or(graphql));
 
+    // Build blobs filter.
+    let blobs_filter = path("blobs")
+        .and(tail_path())
+        .and(get())
+        .and(limit_client(rate_limits, trust_proxy))
+        .and(identify(identifier, api_keys))
+        .and_then(
+            move |tail: Tail, _: Option<IpAddr>, identity: Option<Identity>| {
+                let blobs = blobs.clone();
+                async move {
+                    if identity.is_none() {
+                        let error = format_err!("missing authorization header");
+                        let rejection = custom_rejection(AuthorizationError {
+                            error,
+                            status_code: StatusCode::UNAUTHORIZED,
+                        });
+                        return Err(rejection);
+                    }
+                    let blob = match blobs.get(tail.as_str()).await {
+                        Ok(Some(blob)) => blob,
+                        Ok(None) => return Err(not_found()),
+                        Err(error) => {
+                            let error = Error::from(error);
+                            error!(target: "server", "failed to load blob: {:#}", error);
+                            return Err(custom_rejection(BlobError));
+                        }
+                    };
+                    let Blob { content_type, data } = blob;
+                    let reply = HttpResponse::builder()
+                        .header("content-type", content_type)
+                        .header("cache-control", "private, max-age=3600")
+                        .body(data);
+                    Ok(reply)
+                }
+            },
+        );
+
+    // Build dev token filter, which mints tokens for the local identity
+    // provider.
+    let dev_token_filter = path("dev")
+        .and(path("token"))
+        .and(path_end())
+        .and(post())
+        .and(json_body())
+        .and_then(move |request: DevTokenRequest| {
+            let identifier = local_identifier.clone();
+            async move {
+                let identifier = match identifier {
+                    Some(identifier) => identifier,
+                    None => return Err(not_found()),
+                };
+                let DevTokenRequest { email } = request;
+                let token = identifier.mint(&email).map_err(|error| {
+                    custom_rejection(BadDevTokenRequest(error))
+                })?;
+                let reply = DevTokenReply { token };
+                Ok(reply_json(&reply))
+            }
+        });
+
     // Build root filter.
     let filter = any()
         .and(path_end().and(graphql_playground))
         .or(graphql_filter)
+        .or(blobs_filter)
+        .or(dev_token_filter)
         .recover(|rejection: Rejection| async move {
+            let mut retry_after: Option<StdDuration> = None;
             let (error, status_code) = if rejection.is_not_found() {
-                let error = ServerError::new("not found");
+                let error = ServerError::new(ErrorCode::NotFound, "not found");
                 (error, StatusCode::NOT_FOUND)
+            } else if let Some(BadDevTokenRequest(error)) = rejection.find() {
+                let error = ServerError::new(
+                    ErrorCode::InvalidInput,
+                    format!("{:#}", error),
+                );
+                (error, StatusCode::BAD_REQUEST)
             } else if let Some(BadGraphQLRequest(error)) = rejection.find() {
-                let error = ServerError::new(error.to_string());
+                let error = ServerError::new(
+                    ErrorCode::InvalidInput,
+                    error.to_string(),
+                );
                 (error, StatusCode::BAD_REQUEST)
-            } else if let Some(error) = rejection.find::<Error>() {
-                let error = ServerError::new(format!("{:#}", error));
+            } else if let Some(error) = rejection.find::<AuthorizationError>() {
+                let AuthorizationError { error, status_code } = error;
+                let correlation_id = report_error(error);
+
+                // Requests that were authenticated but not authorized (i.e.
+                // failed impersonations) are forbidden.
+                let (code, message) = match *status_code {
+                    StatusCode::FORBIDDEN => {
+                        (ErrorCode::Forbidden, "not authorized")
+                    }
+                    _ => {
+                        (ErrorCode::NotAuthenticated, "failed to authenticate")
+                    }
+                };
+                let error = ServerError::new(code, message)
+                    .with_correlation_id(correlation_id);
+                (error, *status_code)
+            } else if let Some(RateLimited { retry_after: wait }) =
+                rejection.find()
+            {
+                retry_after = Some(*wait);
+                let error = ServerError::new(
+                    ErrorCode::RateLimited,
+                    "too many requests",
+                );
+                (error, StatusCode::TOO_MANY_REQUESTS)
+            } else if rejection.find::<BlobError>().is_some() {
+                // The error was already logged where it occurred.
+                let error = ServerError::new(
+                    ErrorCode::Internal,
+                    "internal server error",
+                );
                 (error, StatusCode::INTERNAL_SERVER_ERROR)
             } else {
-                let error = ServerError::new("internal server error");
+                let error = ServerError::new(
+                    ErrorCode::Internal,
+                    "internal server error",
+                );
                 (error, StatusCode::INTERNAL_SERVER_ERROR)
             };
 
@@ -166,6 +480,13 @@ async fn main() -> Result<()> {
             };
             let reply = reply_json(&reply);
             let reply = reply_with_status(reply, status_code);
+            let mut reply = reply.into_response();
+            if let Some(retry_after) = retry_after {
+                // Round up, so that clients don't retry too early.
+                let seconds = retry_after.as_secs()
+                    + u64::from(retry_after.subsec_nanos() > 0);
+                reply.headers_mut().insert(RETRY_AFTER, seconds.into());
+            }
             Ok::<_, Infallible>(reply)
         });
 
@@ -182,38 +503,159 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+fn oidc_config() -> Result<OidcConfig> {
+    let issuer = env_var("OIDC_ISSUER").context("failed to get OIDC issuer")?;
+    let audience =
+        env_var("OIDC_AUDIENCE").context("failed to get OIDC audience")?;
+    let jwks_file = env_var("OIDC_JWKS_FILE")
+        .ok()
+        .filter(|path| !path.is_empty());
+    let jwks = match jwks_file {
+        Some(path) => JwksSource::File(path.into()),
+        None => {
+            let uri = env_var("OIDC_JWKS_URI")
+                .context("failed to get OIDC JWKS URI or file")?;
+            JwksSource::Uri(uri)
+        }
+    };
+    let claims = {
+        let ClaimMapping {
+            subject,
+            email,
+            email_verified,
+        } = ClaimMapping::default();
+        ClaimMapping {
+            subject: env_var_or("OIDC_SUBJECT_CLAIM", subject)
+                .context("failed to get OIDC subject claim")?,
+            email: env_var_or("OIDC_EMAIL_CLAIM", email)
+                .context("failed to get OIDC email claim")?,
+            email_verified: env_var_or(
+                "OIDC_EMAIL_VERIFIED_CLAIM",
+                email_verified,
+            )
+            .context("failed to get OIDC email-verified claim")?,
+        }
+    };
+    let config = OidcConfig {
+        issuer,
+        audience,
+        jwks,
+        claims,
+    };
+    Ok(config)
+}
+
+/// Reads a rate limit from the environment variable `name`. An empty limit
+/// disables rate limiting.
+fn rate_limiter(name: &str, default: &str) -> Result<Option<RateLimiter>> {
+    let limit = env_var_or(name, default)
+        .with_context(|| format!("failed to get {}", name))?;
+    if limit.is_empty() {
+        return Ok(None);
+    }
+    let limit = RateLimit::parse(&limit)
+        .with_context(|| format!("failed to parse {}", name))?;
+    Ok(Some(RateLimiter::new(limit)))
+}
+
+/// Extracts the client's IP address, which is read from the
+/// `X-Forwarded-For` header if the server is behind a trusted proxy.
+///
+/// The proxy appends the address it received the request from, so only the
+/// last entry is trusted; earlier ones are supplied by the client.
+fn client_addr(
+    trust_proxy: bool,
+) -> impl Filter<Extract = (Option<IpAddr>,), Error = Rejection> + Clone {
+    remote_addr().and(header("x-forwarded-for")).map(
+        move |remote: Option<SocketAddr>, forwarded: Option<String>| {
+            let forwarded =
+                forwarded.filter(|_| trust_proxy).and_then(|forwarded| {
+                    let client = forwarded.rsplit(',').next()?;
+                    client.trim().parse::<IpAddr>().ok()
+                });
+            forwarded.or_else(|| remote.map(|remote| remote.ip()))
+        },
+    )
+}
+
+/// Extracts the client's IP address, and checks it against the client rate
+/// limit before the request is identified.
+fn limit_client(
+    rate_limits: Arc<RateLimits>,
+    trust_proxy: bool,
+) -> impl Filter<Extract = (Option<IpAddr>,), Error = Rejection> + Clone {
+    client_addr(trust_proxy).and_then(move |addr: Option<IpAddr>| {
+        let rate_limits = rate_limits.clone();
+        async move {
+            if let Some(addr) = addr {
+                rate_limits.check_client(addr).map_err(rate_limited)?;
+            }
+            Ok::<_, Rejection>(addr)
+        }
+    })
+}
+
+fn rate_limited(retry_after: StdDuration) -> Rejection {
+    custom_rejection(RateLimited { retry_after })
+}
+
 fn identify(
-    identifier: Arc<impl Identifier>,
+    identifier: Arc<dyn Identifier>,
+    api_keys: Arc<ApiKeyIdentifier>,
 ) -> impl Filter<Extract = (Option<Identity>,), Error = Rejection> + Clone {
     any()
-        .map(move || identifier.clone())
+        .map(move || (identifier.clone(), api_keys.clone()))
+        .untuple_one()
         .and(header("authorization"))
-        .and_then(|identifier, authorization: Option<String>| async move {
-            let authorization = match authorization {
-                Some(authorization) => authorization,
-                None => return Ok(None),
-            };
-            let token = authorization
-                .strip_prefix("Bearer ")
-                .context("bad authorization header format")
-                .map_err(|error| {
+        .and_then(
+            |identifier: Arc<dyn Identifier>,
+             api_keys: Arc<ApiKeyIdentifier>,
+             authorization: Option<String>| async move {
+                let authorization = match authorization {
+                    Some(authorization) => authorization,
+                    None => return Ok(None),
+                };
+                let token = authorization
+                    .strip_prefix("Bearer ")
+                    .context("bad authorization header format")
+                    .map_err(|error| {
+                        custom_rejection(AuthorizationError {
+                            error,
+                            status_code: StatusCode::BAD_REQUEST,
+                        })
+                    })?;
+                let identity = if ApiKey::is_key(token) {
+                    api_keys
+                        .identify(token)
+                        .await
+                        .context("failed to verify API key")
+                } else {
+                    let identifier = Deref::deref(&identifier);
+                    Identifier::identify(identifier, token)
+                        .await
+                        .context("failed to decode authentication token")
+                };
+                let identity = identity.map_err(|error| {
                     custom_rejection(AuthorizationError {
                         error,
-                        status_code: StatusCode::BAD_REQUEST,
+                        status_code: StatusCode::UNAUTHORIZED,
                     })
                 })?;
-            let identifier = Deref::deref(&identifier);
-            let identity = Identifier::identify(identifier, token)
-                .await
-                .context("failed to decode authentication token")
-                .map_err(|error| {
-                    custom_rejection(AuthorizationError {
-                        error,
-                        status_code: StatusCode::INTERNAL_SERVER_ERROR,
-                    })
-                })?;
-            Result::<_, Rejection>::Ok(Some(identity))
-        })
+                Result::<_, Rejection>::Ok(Some(identity))
+            },
+        )
+}
+
+/// Returns whether the request's document has a mutation. Documents that
+/// can't be parsed are left for the schema to reject.
+fn has_mutation(request: &GraphQLRequest) -> bool {
+    match parse_query(&request.query) {
+        Ok(document) => document
+            .operations
+            .iter()
+            .any(|(_, operation)| operation.node.ty == OperationType::Mutation),
+        Err(_) => false,
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -227,14 +669,33 @@ struct ServerRejectionReply {
 #[serde(rename_all = "camelCase")]
 struct ServerError {
     message: Cow<'static, str>,
+    extensions: ServerErrorExtensions,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ServerErrorExtensions {
+    code: ErrorCode,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    correlation_id: Option<String>,
 }
 
 impl ServerError {
-    fn new(message: impl Into<Cow<'static, str>>) -> Self {
+    fn new(code: ErrorCode, message: impl Into<Cow<'static, str>>) -> Self {
         Self {
             message: message.into(),
+            extensions: ServerErrorExtensions {
+                code,
+                correlation_id: None,
+            },
         }
     }
+
+    fn with_correlation_id(mut self, correlation_id: String) -> Self {
+        self.extensions.correlation_id = Some(correlation_id);
+        self
+    }
 }
 
 #[derive(Debug)]
@@ -244,3 +705,30 @@ struct AuthorizationError {
 }
 
 impl Reject for AuthorizationError {}
+
+#[derive(Debug)]
+struct RateLimited {
+    retry_after: StdDuration,
+}
+
+impl Reject for RateLimited {}
+
+#[derive(Debug)]
+struct BlobError;
+
+impl Reject for BlobError {}
+
+#[derive(Debug, Deserialize)]
+struct DevTokenRequest {
+    email: String,
+}
+
+#[derive(Debug, Serialize)]
+struct DevTokenReply {
+    token: String,
+}
+
+#[derive(Debug)]
+struct BadDevTokenRequest(Error);
+
+impl Reject for BadDevTokenRequest {}
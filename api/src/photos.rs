@@ -0,0 +1,98 @@
+use super::prelude::*;
+
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::io::Reader as ImageReader;
+use image::{load_from_memory_with_format, ImageFormat};
+use std::io::Cursor;
+
+use lattice::blobs::Blob;
+use lattice::entities::ObjectId;
+
+/// The largest profile photo that can be uploaded, in bytes.
+pub const MAX_PHOTO_SIZE: usize = 5 * 1024 * 1024;
+
+/// The largest width or height of a profile photo that will be decoded, in
+/// pixels, since small files can decode to very large images.
+pub const MAX_PHOTO_DIMENSION: u32 = 4096;
+
+/// The MIME types that profile photos can be uploaded as.
+pub const PHOTO_CONTENT_TYPES: &[&str] =
+    &["image/png", "image/jpeg", "image/webp"];
+
+/// The sizes (in pixels) of the square variants generated for each photo.
+pub const PHOTO_SIZES: &[u32] = &[64, 256, 512];
+
+/// The size of the variant used as a user's `photo_url`.
+pub const DEFAULT_PHOTO_SIZE: u32 = 256;
+
+const PHOTO_QUALITY: u8 = 85;
+
+/// Builds public URLs for blobs served by this server.
+#[derive(Debug, Clone)]
+pub struct BlobUrls {
+    base_url: String,
+}
+
+impl BlobUrls {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        let base_url: String = base_url.into();
+        let base_url = base_url.trim_end_matches('/').to_owned();
+        Self { base_url }
+    }
+
+    pub fn url(&self, key: &str) -> String {
+        format!("{}/blobs/{}", self.base_url, key)
+    }
+}
+
+pub fn photo_key(user_id: &ObjectId, size: u32) -> String {
+    format!("users/{}/photo/{}.jpg", user_id, size)
+}
+
+/// Decodes an uploaded photo, and renders it as a square JPEG at each of
+/// `PHOTO_SIZES`.
+///
+/// This is CPU-bound, so it should be run on a blocking thread.
+pub fn render_photo_variants(data: &[u8]) -> Result<Vec<(u32, Blob)>> {
+    let reader = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .context("failed to read image")?;
+    let format = reader.format().context("unrecognized image format")?;
+    match format {
+        ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::WebP => {}
+        format => bail!("unsupported image format: {:?}", format),
+    }
+
+    // Check the image's dimensions before decoding it.
+    let (width, height) = reader
+        .into_dimensions()
+        .context("failed to read image dimensions")?;
+    if width > MAX_PHOTO_DIMENSION || height > MAX_PHOTO_DIMENSION {
+        bail!(
+            "image must be at most {} pixels wide and tall",
+            MAX_PHOTO_DIMENSION
+        );
+    }
+    let image = load_from_memory_with_format(data, format)
+        .context("failed to decode image")?;
+
+    PHOTO_SIZES
+        .iter()
+        .map(|&size| {
+            let variant =
+                image.resize_to_fill(size, size, FilterType::Lanczos3);
+            let mut data = Vec::new();
+            let mut encoder =
+                JpegEncoder::new_with_quality(&mut data, PHOTO_QUALITY);
+            encoder
+                .encode_image(&variant)
+                .context("failed to encode image")?;
+            let blob = Blob {
+                content_type: "image/jpeg".to_owned(),
+                data,
+            };
+            Ok((size, blob))
+        })
+        .collect()
+}
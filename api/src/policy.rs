@@ -0,0 +1,69 @@
+use super::prelude::*;
+
+use lattice::entities::AccountType;
+
+/// Decides which emails may register, and with which account type.
+#[derive(Debug, Clone)]
+pub struct EmailPolicy {
+    /// Domains whose addresses may register as members.
+    domains: Vec<String>,
+
+    /// Individual addresses that may register, and their account types.
+    emails: Map<String, AccountType>,
+}
+
+impl EmailPolicy {
+    /// Parses a policy from a comma-separated list of domains (i.e.
+    /// "uwblueprint.org"), and a comma-separated list of addresses, each
+    /// with an optional account type (i.e. "jane@gmail.com:Alumni").
+    ///
+    /// Addresses without an account type are partner contacts.
+    pub fn parse(domains: &str, emails: &str) -> Result<Self> {
+        let domains: Vec<String> = split_list(domains)
+            .map(|domain| domain.trim_start_matches('@').to_lowercase())
+            .collect();
+        let emails = split_list(emails)
+            .map(|entry| {
+                let mut parts = entry.splitn(2, ':');
+                let email = parts.next().unwrap_or(entry);
+                let account_type = match parts.next() {
+                    Some(account_type) => {
+                        account_type.trim().parse().with_context(|| {
+                            format!("invalid account type for {}", email)
+                        })?
+                    }
+                    None => AccountType::PartnerContact,
+                };
+                let email = email.trim().to_lowercase();
+                if !email.contains('@') {
+                    bail!("invalid email: {}", email);
+                }
+                Ok((email, account_type))
+            })
+            .collect::<Result<_>>()?;
+        let policy = Self { domains, emails };
+        Ok(policy)
+    }
+
+    /// Returns the account type that `email` may register with, or `None` if
+    /// it may not register.
+    pub fn account_type(&self, email: &str) -> Option<AccountType> {
+        let email = email.to_lowercase();
+        if let Some(account_type) = self.emails.get(&email) {
+            return Some(*account_type);
+        }
+        // Take the domain after the last "@", if there is one.
+        let mut parts = email.rsplitn(2, '@');
+        let (domain, _) = (parts.next()?, parts.next()?);
+        if self.domains.iter().any(|allowed| allowed == domain) {
+            return Some(AccountType::Member);
+        }
+        None
+    }
+}
+
+fn split_list(list: &str) -> impl Iterator<Item = &str> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+}
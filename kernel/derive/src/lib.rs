@@ -0,0 +1,250 @@
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+
+use syn::parse_macro_input;
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Error, Fields, FieldsNamed, Lit, Meta};
+use syn::{NestedMeta, Path, Result};
+
+/// Derives `Object` (and `Entity`, if a collection is given) for a kernel
+/// entity struct.
+///
+/// The struct must have an `id: ObjectId` field. Supported attributes:
+///
+/// - `#[entity(collection = "...")]`: the MongoDB collection name. If
+///   omitted, `Entity` is left to be implemented by hand (i.e. to override
+///   its hooks).
+/// - `#[entity(object_type = "...")]`: the name of the `ObjectType`, which
+///   defaults to the name of the struct.
+/// - `#[entity(crate = "...")]`: the path to the kernel crate, which defaults
+///   to `lattice_kernel`.
+/// - `#[entity(date)]` (on a field): stores a `Date` field as a BSON
+///   date-time.
+/// - `#[entity(date = "...")]` (on a field): stores the named `Date` field of
+///   a nested struct (or of each struct in a `Vec`) as a BSON date-time.
+/// - `#[entity(date_time)]` (on a field): stores a `DateTime` field as a BSON
+///   date-time.
+/// - `#[entity(natural_key)]` (on a field): marks a field that uniquely
+///   identifies an entity.
+/// - `#[entity(search)]` (on a field): indexes a string field for prefix and
+///   fuzzy search.
+#[proc_macro_derive(Entity, attributes(entity))]
+pub fn derive_entity(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream2> {
+    let DeriveInput {
+        ident,
+        attrs,
+        data,
+        generics,
+        ..
+    } = input;
+
+    let mut collection: Option<String> = None;
+    let mut object_type = ident.to_string();
+    let mut krate: Path = syn::parse_quote!(::lattice_kernel);
+    for meta in entity_attrs(&attrs)? {
+        match meta {
+            Meta::NameValue(meta) if meta.path.is_ident("collection") => {
+                collection = Some(lit_str(&meta.lit)?);
+            }
+            Meta::NameValue(meta) if meta.path.is_ident("object_type") => {
+                object_type = lit_str(&meta.lit)?;
+            }
+            Meta::NameValue(meta) if meta.path.is_ident("crate") => {
+                let path = lit_str(&meta.lit)?;
+                krate = syn::parse_str(&path)
+                    .map_err(|_| Error::new(meta.lit.span(), "invalid path"))?;
+            }
+            meta => {
+                let message = "unknown entity attribute";
+                return Err(Error::new(meta.span(), message));
+            }
+        }
+    }
+
+    let fields = match data {
+        Data::Struct(data) => match data.fields {
+            Fields::Named(fields) => fields.named,
+            fields => {
+                let message = "entities must have named fields";
+                return Err(Error::new(fields.span(), message));
+            }
+        },
+        _ => {
+            let message = "entities must be structs";
+            return Err(Error::new(Span::call_site(), message));
+        }
+    };
+
+    let mut has_id = false;
+    let mut date_fields = Vec::new();
+    let mut date_time_fields = Vec::new();
+    let mut natural_keys = Vec::new();
+    let mut search_fields = Vec::new();
+    for field in &fields {
+        let name = match &field.ident {
+            Some(name) => name,
+            None => continue,
+        };
+        if name == "id" {
+            has_id = true;
+        }
+        for meta in entity_attrs(&field.attrs)? {
+            match meta {
+                Meta::Path(path) if path.is_ident("date") => {
+                    date_fields.push(name.to_string());
+                }
+                Meta::NameValue(meta) if meta.path.is_ident("date") => {
+                    let field = lit_str(&meta.lit)?;
+                    date_fields.push(format!("{}.{}", name, field));
+                }
+                Meta::Path(path) if path.is_ident("date_time") => {
+                    date_time_fields.push(name.to_string());
+                }
+                Meta::Path(path) if path.is_ident("natural_key") => {
+                    natural_keys.push(name.to_string());
+                }
+                Meta::Path(path) if path.is_ident("search") => {
+                    search_fields.push(name.to_string());
+                }
+                meta => {
+                    let message = "unknown entity field attribute";
+                    return Err(Error::new(meta.span(), message));
+                }
+            }
+        }
+    }
+    if !has_id {
+        let message = "entities must have an `id` field";
+        return Err(Error::new(ident.span(), message));
+    }
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let entities = quote! { #krate::entities };
+    let mut tokens = quote! {
+        impl #impl_generics #entities::Object for #ident #ty_generics
+        #where_clause
+        {
+            const OBJECT_TYPE: #entities::ObjectType =
+                #entities::ObjectType::new(#object_type);
+
+            const DATE_FIELDS: &'static [&'static str] = &[#(#date_fields),*];
+
+            const DATE_TIME_FIELDS: &'static [&'static str] =
+                &[#(#date_time_fields),*];
+
+            const SEARCH_FIELDS: &'static [&'static str] =
+                &[#(#search_fields),*];
+
+            fn object_id(&self) -> #entities::ObjectId {
+                ::std::clone::Clone::clone(&self.id)
+            }
+        }
+    };
+    if let Some(collection) = collection {
+        tokens.extend(quote! {
+            impl #impl_generics #entities::Entity for #ident #ty_generics
+            #where_clause
+            {
+                const COLLECTION_NAME: &'static str = #collection;
+
+                const NATURAL_KEYS: &'static [&'static str] =
+                    &[#(#natural_keys),*];
+            }
+        });
+    }
+    Ok(tokens)
+}
+
+/// Adds the fields that every entity has to an entity struct: `id`,
+/// `created_at`, and `updated_at`, which are skipped by its builder.
+///
+/// Must be placed above the struct's `#[derive(...)]`, so that the derives
+/// see the added fields.
+#[proc_macro_attribute]
+pub fn entity_fields(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = TokenStream2::from(args);
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_fields(args, input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand_fields(
+    args: TokenStream2,
+    mut input: DeriveInput,
+) -> Result<TokenStream2> {
+    if !args.is_empty() {
+        let message = "entity_fields doesn't take arguments";
+        return Err(Error::new(args.span(), message));
+    }
+
+    let fields = match &mut input.data {
+        Data::Struct(data) => match &mut data.fields {
+            Fields::Named(fields) => &mut fields.named,
+            fields => {
+                let message = "entities must have named fields";
+                return Err(Error::new(fields.span(), message));
+            }
+        },
+        _ => {
+            let message = "entities must be structs";
+            return Err(Error::new(Span::call_site(), message));
+        }
+    };
+    let common: FieldsNamed = syn::parse_quote!({
+        #[builder(default, setter(skip))]
+        pub id: ::lattice_kernel::entities::ObjectId,
+
+        #[builder(default = ::chrono::Utc::now(), setter(skip))]
+        pub created_at: ::chrono::DateTime<::chrono::Utc>,
+
+        #[builder(default = ::chrono::Utc::now(), setter(skip))]
+        pub updated_at: ::chrono::DateTime<::chrono::Utc>,
+    });
+    let mut named = common.named;
+    named.extend(std::mem::take(fields));
+    *fields = named;
+    Ok(quote! { #input })
+}
+
+fn entity_attrs(attrs: &[syn::Attribute]) -> Result<Vec<Meta>> {
+    let mut metas = Vec::new();
+    for attr in attrs {
+        if !attr.path.is_ident("entity") {
+            continue;
+        }
+        match attr.parse_meta()? {
+            Meta::List(list) => {
+                for nested in list.nested {
+                    match nested {
+                        NestedMeta::Meta(meta) => metas.push(meta),
+                        NestedMeta::Lit(lit) => {
+                            let message = "unexpected literal";
+                            return Err(Error::new(lit.span(), message));
+                        }
+                    }
+                }
+            }
+            meta => {
+                let message = "expected `#[entity(...)]`";
+                return Err(Error::new(meta.span(), message));
+            }
+        }
+    }
+    Ok(metas)
+}
+
+fn lit_str(lit: &Lit) -> Result<String> {
+    match lit {
+        Lit::Str(lit) => Ok(lit.value()),
+        lit => Err(Error::new(lit.span(), "expected string literal")),
+    }
+}
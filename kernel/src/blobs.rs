@@ -0,0 +1,112 @@
+use crate::prelude::*;
+
+use std::io::ErrorKind as IoErrorKind;
+use std::path::PathBuf;
+use tokio::fs;
+
+/// A binary object and its MIME type.
+#[derive(Debug, Clone)]
+pub struct Blob {
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+/// Storage for binary objects, addressed by `/`-separated keys (i.e.
+/// `users/<id>/photo/256.jpg`).
+#[async_trait]
+pub trait BlobStore: Send + Sync {
+    async fn get(&self, key: &str) -> Result<Option<Blob>>;
+    async fn put(&self, key: &str, blob: Blob) -> Result<()>;
+    async fn delete(&self, key: &str) -> Result<()>;
+}
+
+/// A `BlobStore` that keeps blobs as files under a root directory.
+///
+/// Each blob's content type is stored alongside it, in a file with the
+/// extension `.content-type`.
+#[derive(Debug, Clone)]
+pub struct FileBlobStore {
+    root: PathBuf,
+}
+
+const CONTENT_TYPE_EXTENSION: &str = "content-type";
+
+impl FileBlobStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn paths(&self, key: &str) -> Result<(PathBuf, PathBuf)> {
+        validate_key(key)?;
+        let path = self.root.join(key);
+        let mut content_type_path = path.clone().into_os_string();
+        content_type_path.push(".");
+        content_type_path.push(CONTENT_TYPE_EXTENSION);
+        Ok((path, content_type_path.into()))
+    }
+}
+
+#[async_trait]
+impl BlobStore for FileBlobStore {
+    async fn get(&self, key: &str) -> Result<Option<Blob>> {
+        let (path, content_type_path) = self.paths(key)?;
+        let data = match fs::read(&path).await {
+            Ok(data) => data,
+            Err(error) if error.kind() == IoErrorKind::NotFound => {
+                return Ok(None)
+            }
+            Err(error) => return Err(Error::internal(error)),
+        };
+        let content_type = fs::read_to_string(&content_type_path)
+            .await
+            .map_err(Error::internal)?;
+        let blob = Blob { content_type, data };
+        Ok(Some(blob))
+    }
+
+    async fn put(&self, key: &str, blob: Blob) -> Result<()> {
+        let (path, content_type_path) = self.paths(key)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await.map_err(Error::internal)?;
+        }
+        let Blob { content_type, data } = blob;
+        fs::write(&path, data).await.map_err(Error::internal)?;
+        fs::write(&content_type_path, content_type)
+            .await
+            .map_err(Error::internal)?;
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        let (path, content_type_path) = self.paths(key)?;
+        for path in &[path, content_type_path] {
+            match fs::remove_file(path).await {
+                Ok(()) => {}
+                Err(error) if error.kind() == IoErrorKind::NotFound => {}
+                Err(error) => return Err(Error::internal(error)),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Checks that a key is made up of non-empty segments of letters, digits,
+/// `.`, `-`, and `_`, so that it can't escape a store's root, and that it
+/// doesn't name a content type file.
+fn validate_key(key: &str) -> Result<()> {
+    let content_type_suffix = format!(".{}", CONTENT_TYPE_EXTENSION);
+    let is_valid = !key.is_empty()
+        && !key.ends_with(&content_type_suffix)
+        && key.split('/').all(|segment| {
+            !segment.is_empty()
+                && !segment.starts_with('.')
+                && segment.chars().all(|c| {
+                    c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_')
+                })
+        });
+    if !is_valid {
+        let message = format!("invalid blob key: {}", key);
+        return Err(Error::validation(message));
+    }
+    Ok(())
+}
@@ -1,35 +1,110 @@
 mod prelude;
 
 mod build;
+mod bulk;
+mod cache;
 mod date;
+mod events;
+mod identity;
+mod impersonation;
+mod invitation;
 mod membership;
 mod meta;
+mod search;
+mod service;
+mod skill;
+mod team;
+mod term;
 mod user;
 
 pub use build::*;
+pub use bulk::{BulkItem, BulkOutcome, BulkReport};
+pub use cache::{CacheStats, EntityCache};
+pub use events::{EntityEvent, EventBus};
+pub use identity::*;
+pub use impersonation::*;
+pub use invitation::*;
 pub use membership::*;
 pub use meta::*;
+pub use search::{GlobalSearchQuery, SearchHit};
+pub use search::{SearchHighlight, SearchQuery, SearchResult};
+pub use service::*;
+pub use skill::*;
+pub use team::*;
+pub use term::*;
 pub use user::*;
 
+pub use lattice_kernel_derive::{entity_fields, Entity};
+
 use prelude::*;
 
-use mongodb::options::{FindOneOptions, FindOptions, ReplaceOptions};
+use crate::blobs::BlobStore;
+
+use mongodb::options::{FindOneAndDeleteOptions, FindOneAndReplaceOptions};
+use mongodb::options::{FindOneOptions, FindOptions};
+use mongodb::options::{ReplaceOptions, ReturnDocument};
 
 pub struct Context {
     database: Database,
+    cache: Option<EntityCache>,
+    events: EventBus,
+    blobs: Option<Arc<dyn BlobStore>>,
 }
 
 impl Context {
     pub fn new(database: Database) -> Self {
-        Self { database }
+        Self {
+            database,
+            cache: None,
+            events: EventBus::new(),
+            blobs: None,
+        }
+    }
+
+    pub fn with_cache(self, cache: EntityCache) -> Self {
+        Self {
+            cache: Some(cache),
+            ..self
+        }
+    }
+
+    pub fn cache(&self) -> Option<&EntityCache> {
+        self.cache.as_ref()
+    }
+
+    pub fn events(&self) -> &EventBus {
+        &self.events
+    }
+
+    pub fn with_blobs(self, blobs: Arc<dyn BlobStore>) -> Self {
+        Self {
+            blobs: Some(blobs),
+            ..self
+        }
+    }
+
+    pub fn blobs(&self) -> Option<&dyn BlobStore> {
+        self.blobs.as_deref()
     }
 }
 
 pub trait Object:
-    Debug + Clone + Serialize + DeserializeOwned + Send + Unpin
+    Debug + Clone + Serialize + DeserializeOwned + Send + Sync + Unpin + 'static
 {
     const OBJECT_TYPE: ObjectType;
 
+    /// Fields of type `Date` that are stored as BSON date-times. Fields of
+    /// nested documents (or of each document in an array) are named by their
+    /// dotted path, i.e. "skills.last_used".
+    const DATE_FIELDS: &'static [&'static str] = &[];
+
+    /// Fields of type `DateTime` that are stored as BSON date-times, so that
+    /// they can be compared in queries.
+    const DATE_TIME_FIELDS: &'static [&'static str] = &[];
+
+    /// String fields that are tokenized for prefix and fuzzy search.
+    const SEARCH_FIELDS: &'static [&'static str] = &[];
+
     fn object_id(&self) -> ObjectId;
 
     fn object_ref(&self) -> ObjectRef {
@@ -45,15 +120,22 @@ pub trait Object:
         let mut doc = to_document(self)?;
 
         // Normalize ID field.
-        let id = doc.remove("id").expect("missing `id` field");
+        let id = doc
+            .remove("id")
+            .ok_or_else(|| Error::internal("missing `id` field"))?;
         doc.insert("_id", id);
 
         // Normalize created-at timestamp.
         if let Some(created_at) = doc.get("created_at") {
             if let Bson::String(created_at) = created_at {
-                let created_at: DateTime = created_at
-                    .parse()
-                    .context("failed to parse created-at timestamp")?;
+                let created_at: DateTime =
+                    created_at.parse().map_err(|error| {
+                        let message = format!(
+                            "failed to parse created-at timestamp: {}",
+                            error
+                        );
+                        Error::internal(message)
+                    })?;
                 doc.insert("created_at", Bson::DateTime(created_at));
             }
         };
@@ -61,21 +143,76 @@ pub trait Object:
         // Normalize updated-at timestamp.
         if let Some(updated_at) = doc.get("updated_at") {
             if let Bson::String(updated_at) = updated_at {
-                let updated_at: DateTime = updated_at
-                    .parse()
-                    .context("failed to parse updated-at timestamp")?;
+                let updated_at: DateTime =
+                    updated_at.parse().map_err(|error| {
+                        let message = format!(
+                            "failed to parse updated-at timestamp: {}",
+                            error
+                        );
+                        Error::internal(message)
+                    })?;
                 doc.insert("updated_at", Bson::DateTime(updated_at));
             }
         };
 
+        // Normalize date fields.
+        for field in Self::DATE_FIELDS {
+            date::visit_field(&mut doc, field, &mut |value| {
+                let date: Date = match value {
+                    Bson::String(date) => date.parse().map_err(|error| {
+                        let message = format!(
+                            "failed to parse date field `{}`: {}",
+                            field, error
+                        );
+                        Error::internal(message)
+                    })?,
+                    _ => return Ok(()),
+                };
+                *value = Bson::DateTime(date::to_date_time(date));
+                Ok(())
+            })?;
+        }
+
+        // Normalize date-time fields.
+        for field in Self::DATE_TIME_FIELDS {
+            date::visit_field(&mut doc, field, &mut |value| {
+                let date_time: DateTime = match value {
+                    Bson::String(date_time) => {
+                        date_time.parse().map_err(|error| {
+                            let message = format!(
+                                "failed to parse date-time field `{}`: {}",
+                                field, error
+                            );
+                            Error::internal(message)
+                        })?
+                    }
+                    _ => return Ok(()),
+                };
+                *value = Bson::DateTime(date_time);
+                Ok(())
+            })?;
+        }
+
+        // Derive search tokens.
+        if !Self::SEARCH_FIELDS.is_empty() {
+            let values = search::field_values(&doc, Self::SEARCH_FIELDS);
+            let tokens = search::index_tokens(values);
+            doc.insert(search::SEARCH_TOKENS_FIELD, tokens);
+        }
+
         Ok(doc)
     }
 
     fn from_document(mut doc: Document) -> Result<Self> {
         // Normalize ID field.
-        let id = doc.remove("_id").expect("missing `_id` field");
+        let id = doc
+            .remove("_id")
+            .ok_or_else(|| Error::internal("missing `_id` field"))?;
         doc.insert("id", id);
 
+        // Drop derived search tokens.
+        doc.remove(search::SEARCH_TOKENS_FIELD);
+
         // Normalize created-at timestamp.
         if let Some(created_at) = doc.get("created_at") {
             if let Bson::DateTime(created_at) = created_at {
@@ -92,6 +229,27 @@ pub trait Object:
             }
         };
 
+        // Normalize date fields.
+        for field in Self::DATE_FIELDS {
+            date::visit_field(&mut doc, field, &mut |value| {
+                if let Bson::DateTime(date_time) = value {
+                    let date = date::from_date_time(*date_time);
+                    *value = Bson::String(date.to_string());
+                }
+                Ok(())
+            })?;
+        }
+
+        // Normalize date-time fields.
+        for field in Self::DATE_TIME_FIELDS {
+            date::visit_field(&mut doc, field, &mut |value| {
+                if let Bson::DateTime(date_time) = value {
+                    *value = Bson::String(date_time.to_string());
+                }
+                Ok(())
+            })?;
+        }
+
         let object = from_document(doc)?;
         Ok(object)
     }
@@ -101,6 +259,10 @@ pub trait Object:
 pub trait Entity: Object {
     const COLLECTION_NAME: &'static str;
 
+    /// Fields that uniquely identify an entity, which can be used to look it
+    /// up from the cache.
+    const NATURAL_KEYS: &'static [&'static str] = &[];
+
     fn collection(ctx: &Context) -> Collection<Document> {
         let name = Self::COLLECTION_NAME;
         ctx.database.collection(name)
@@ -122,19 +284,73 @@ pub trait Entity: Object {
         FindQuery::new(conditions)
     }
 
+    fn search(query: impl Into<String>) -> SearchQuery<Self> {
+        SearchQuery::new(query)
+    }
+
     async fn save(&mut self, ctx: &Context) -> Result<()> {
         self.before_save(ctx).await?;
         let collection = Self::collection(ctx);
         let doc = self.to_document()?;
         let id = self.object_id();
-        let options = ReplaceOptions::builder().upsert(true).build();
-        collection
-            .replace_one(doc! { "_id": id }, doc, options)
-            .await?;
+
+        // Only fetch the previous document if someone is listening for it.
+        let listening = ctx.events.has_subscribers::<Self>();
+        let old = if listening {
+            let options = FindOneAndReplaceOptions::builder()
+                .upsert(true)
+                .return_document(ReturnDocument::Before)
+                .build();
+            collection
+                .find_one_and_replace(doc! { "_id": &id }, doc, options)
+                .await?
+        } else {
+            let options = ReplaceOptions::builder().upsert(true).build();
+            collection
+                .replace_one(doc! { "_id": &id }, doc, options)
+                .await?;
+            None
+        };
+        if let Some(cache) = &ctx.cache {
+            cache.invalidate::<Self>(&id);
+        }
         self.after_save(ctx).await?;
+
+        // The write is committed by now, so failing to decode the previous
+        // document only skips the event.
+        if listening {
+            let id = self.global_id();
+            let new = self.clone();
+            let event = match old.map(Self::from_document).transpose() {
+                Ok(Some(old)) => Some(EntityEvent::Updated { id, old, new }),
+                Ok(None) => Some(EntityEvent::Created { id, new }),
+                Err(error) => {
+                    warn!(%id, "failed to decode previous entity: {:#}", error);
+                    None
+                }
+            };
+            if let Some(event) = event {
+                ctx.events.publish(event).await;
+            }
+        }
         Ok(())
     }
 
+    async fn save_many(
+        items: &mut [Self],
+        ctx: &Context,
+    ) -> Result<BulkReport> {
+        bulk::save_many(ctx, items, None).await
+    }
+
+    async fn upsert_by(
+        natural_key: &[&str],
+        items: &mut [Self],
+        ctx: &Context,
+    ) -> Result<BulkReport> {
+        bulk::save_many(ctx, items, Some(natural_key)).await
+    }
+
     async fn before_save(&mut self, _: &Context) -> Result<()> {
         Ok(())
     }
@@ -147,11 +363,48 @@ pub trait Entity: Object {
         self.before_delete(ctx).await?;
         let collection = Self::collection(ctx);
         let id = self.object_id();
-        collection.delete_one(doc! { "_id": id }, None).await?;
+
+        // Only fetch the deleted document if someone is listening for it.
+        let listening = ctx.events.has_subscribers::<Self>();
+        let old = if listening {
+            let options = FindOneAndDeleteOptions::default();
+            collection
+                .find_one_and_delete(doc! { "_id": &id }, options)
+                .await?
+        } else {
+            collection.delete_one(doc! { "_id": &id }, None).await?;
+            None
+        };
+        if let Some(cache) = &ctx.cache {
+            cache.invalidate::<Self>(&id);
+        }
         self.after_delete(ctx).await?;
+
+        // The document is deleted by now, so failing to decode it only skips
+        // the event.
+        if let Some(old) = old {
+            let id = self.global_id();
+            match Self::from_document(old) {
+                Ok(old) => {
+                    let event = EntityEvent::Deleted { id, old };
+                    ctx.events.publish(event).await;
+                }
+                Err(error) => {
+                    warn!(%id, "failed to decode deleted entity: {:#}", error);
+                }
+            }
+        }
         Ok(())
     }
 
+    async fn delete_many(
+        conditions: impl Into<Document> + Send + 'static,
+        ctx: &Context,
+    ) -> Result<BulkReport> {
+        let conditions: Document = conditions.into();
+        bulk::delete_many::<Self>(ctx, conditions).await
+    }
+
     async fn before_delete(&mut self, _: &Context) -> Result<()> {
         Ok(())
     }
@@ -184,13 +437,29 @@ impl<T: Entity> FindOneQuery<T> {
             options,
             ..
         } = self;
+
+        // Try to load the entity from the cache.
+        let cache = ctx.cache.as_ref().and_then(|cache| {
+            let key = EntityCache::key::<T>(&conditions)?;
+            Some((cache, key))
+        });
+        if let Some((cache, key)) = &cache {
+            if let Some(doc) = cache.get(key) {
+                let entity = T::from_document(doc)?;
+                return Ok(Some(entity));
+            }
+        }
+
         let collection = T::collection(ctx);
         let doc = collection.find_one(conditions, options).await?;
         let doc = match doc {
             Some(doc) => doc,
             None => return Ok(None),
         };
-        let entity = T::from_document(doc)?;
+        let entity = T::from_document(doc.clone())?;
+        if let Some((cache, key)) = cache {
+            cache.insert::<T>(key, entity.object_id(), doc);
+        }
         Ok(Some(entity))
     }
 
@@ -309,13 +578,18 @@ impl From<SortingOrder> for Bson {
     }
 }
 
-impl From<Bson> for SortingOrder {
-    fn from(bson: Bson) -> Self {
+impl TryFrom<Bson> for SortingOrder {
+    type Error = Error;
+
+    fn try_from(bson: Bson) -> Result<Self> {
         use SortingOrder::*;
         match bson {
-            Bson::Int32(1) => Asc,
-            Bson::Int32(-1) => Desc,
-            other => panic!("invalid sorting order: {}", other),
+            Bson::Int32(1) => Ok(Asc),
+            Bson::Int32(-1) => Ok(Desc),
+            other => {
+                let message = format!("invalid sorting order: {}", other);
+                Err(Error::validation(message))
+            }
         }
     }
 }
@@ -0,0 +1,444 @@
+use super::prelude::*;
+
+use futures::TryStreamExt;
+use std::collections::HashSet;
+
+const BULK_BATCH_SIZE: usize = 1000;
+
+#[derive(Debug, Clone, Default)]
+pub struct BulkReport {
+    pub items: Vec<BulkItem>,
+}
+
+impl BulkReport {
+    pub fn succeeded(&self) -> impl Iterator<Item = &BulkItem> {
+        self.items.iter().filter(|item| !item.is_failed())
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = &BulkItem> {
+        self.items.iter().filter(|item| item.is_failed())
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.failed().next().is_none()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BulkItem {
+    pub id: ObjectId,
+    pub outcome: BulkOutcome,
+}
+
+impl BulkItem {
+    pub fn is_failed(&self) -> bool {
+        matches!(self.outcome, BulkOutcome::Failed(_))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BulkOutcome {
+    Inserted,
+    Updated,
+    Deleted,
+    Failed(String),
+}
+
+pub(crate) async fn save_many<T: Entity>(
+    ctx: &Context,
+    items: &mut [T],
+    natural_key: Option<&[&str]>,
+) -> Result<BulkReport> {
+    let mut report = BulkReport::default();
+    for batch in items.chunks_mut(BULK_BATCH_SIZE) {
+        let outcomes = save_batch(ctx, batch, natural_key).await?;
+        let items = batch.iter().zip(outcomes).map(|(item, outcome)| {
+            let id = item.object_id();
+            BulkItem { id, outcome }
+        });
+        report.items.extend(items);
+    }
+    Ok(report)
+}
+
+async fn save_batch<T: Entity>(
+    ctx: &Context,
+    items: &mut [T],
+    natural_key: Option<&[&str]>,
+) -> Result<Vec<BulkOutcome>> {
+    let mut outcomes: Vec<Option<BulkOutcome>> = vec![None; items.len()];
+
+    // Run pre-save hooks, and build a document for each item.
+    let mut docs: Vec<Option<Document>> = Vec::with_capacity(items.len());
+    for (item, outcome) in items.iter_mut().zip(&mut outcomes) {
+        let doc = match item.before_save(ctx).await {
+            Ok(()) => item.to_document(),
+            Err(error) => Err(error),
+        };
+        match doc {
+            Ok(doc) => docs.push(Some(doc)),
+            Err(error) => {
+                *outcome = Some(failed(error));
+                docs.push(None);
+            }
+        }
+    }
+
+    // Build the filter that selects the stored document for each item.
+    let mut filters: Vec<Option<Document>> = Vec::with_capacity(items.len());
+    for (doc, outcome) in docs.iter().zip(&mut outcomes) {
+        let doc = match doc {
+            Some(doc) => doc,
+            None => {
+                filters.push(None);
+                continue;
+            }
+        };
+        let filter = match natural_key {
+            Some(fields) => key_filter(doc, fields),
+            None => match doc.get("_id") {
+                Some(id) => Ok(doc! { "_id": id.clone() }),
+                None => Err(Error::internal("missing `_id` field")),
+            },
+        };
+        match filter {
+            Ok(filter) => filters.push(Some(filter)),
+            Err(error) => {
+                *outcome = Some(failed(error));
+                filters.push(None);
+            }
+        }
+    }
+
+    // Reject items whose natural key repeats an earlier item's, since both
+    // would be inserted if the key isn't stored yet.
+    if natural_key.is_some() {
+        let mut seen = HashSet::new();
+        for (filter, outcome) in filters.iter_mut().zip(&mut outcomes) {
+            let key = match filter {
+                Some(filter) => filter.to_string(),
+                None => continue,
+            };
+            if !seen.insert(key) {
+                let error = Error::validation("duplicate natural key in batch");
+                *outcome = Some(failed(error));
+                *filter = None;
+            }
+        }
+    }
+
+    // Items matched by natural key take on the identity of the stored
+    // document, so that replacing it never changes its `_id`.
+    if natural_key.is_some() {
+        let existing = {
+            let filters: Vec<_> = filters.iter().flatten().cloned().collect();
+            find_existing::<T>(ctx, filters).await?
+        };
+        for (i, item) in items.iter_mut().enumerate() {
+            let (doc, filter) = match (&mut docs[i], &filters[i]) {
+                (Some(doc), Some(filter)) => (doc, filter),
+                _ => continue,
+            };
+            let stored = match existing.get(&filter.to_string()) {
+                Some(stored) => stored,
+                None => continue,
+            };
+            for field in &["_id", "created_at"] {
+                if let Some(value) = stored.get(field) {
+                    doc.insert(*field, value.clone());
+                }
+            }
+            match T::from_document(doc.clone()) {
+                Ok(adopted) => *item = adopted,
+                Err(error) => outcomes[i] = Some(failed(error)),
+            }
+        }
+    }
+
+    // Fetch the previous documents if someone is listening for them.
+    let listening = ctx.events.has_subscribers::<T>();
+    let mut previous = Map::new();
+    if listening {
+        let ids: Vec<ObjectId> = items
+            .iter()
+            .zip(&outcomes)
+            .filter(|(_, outcome)| outcome.is_none())
+            .map(|(item, _)| item.object_id())
+            .collect();
+        if !ids.is_empty() {
+            let collection = T::collection(ctx);
+            let conditions = doc! { "_id": { "$in": ids } };
+            let mut cursor = collection.find(conditions, None).await?;
+            while let Some(doc) = cursor.next().await {
+                let doc = doc?;
+                if let Ok(id) = doc.get_object_id("_id") {
+                    previous.insert(id.to_owned(), doc);
+                }
+            }
+        }
+    }
+
+    // Replace (or insert) every item that is still pending.
+    let mut indices = Vec::new();
+    let mut statements = Vec::new();
+    for (i, (doc, filter)) in docs.into_iter().zip(filters).enumerate() {
+        if outcomes[i].is_some() {
+            continue;
+        }
+        if let (Some(doc), Some(filter)) = (doc, filter) {
+            indices.push(i);
+            statements.push(doc! { "q": filter, "u": doc, "upsert": true });
+        }
+    }
+    let result = write::<T>(ctx, "update", "updates", statements).await?;
+    for (n, i) in indices.into_iter().enumerate() {
+        let outcome = if let Some(message) = result.errors.get(&n) {
+            BulkOutcome::Failed(message.to_owned())
+        } else if result.upserted.contains(&n) {
+            BulkOutcome::Inserted
+        } else {
+            BulkOutcome::Updated
+        };
+        outcomes[i] = Some(outcome);
+    }
+    invalidate(ctx, items, &outcomes);
+
+    // Run post-save hooks for items that were written.
+    for (item, outcome) in items.iter_mut().zip(&mut outcomes) {
+        if let Some(BulkOutcome::Failed(_)) = outcome {
+            continue;
+        }
+        if let Err(error) = item.after_save(ctx).await {
+            *outcome = Some(failed(error));
+        }
+    }
+
+    // Publish events for items that were written.
+    if listening {
+        for (item, outcome) in items.iter().zip(&outcomes) {
+            if let Some(BulkOutcome::Failed(_)) | None = outcome {
+                continue;
+            }
+            let id = item.global_id();
+            let new = item.clone();
+            let old = previous.remove(&item.object_id());
+            let event = match old.map(T::from_document).transpose() {
+                Ok(Some(old)) => EntityEvent::Updated { id, old, new },
+                Ok(None) => EntityEvent::Created { id, new },
+                Err(error) => {
+                    // The item is written, so just skip its event.
+                    warn!(%id, "failed to decode previous entity: {:#}", error);
+                    continue;
+                }
+            };
+            ctx.events.publish(event).await;
+        }
+    }
+
+    let outcomes = outcomes
+        .into_iter()
+        .map(|outcome| outcome.expect("missing bulk outcome"))
+        .collect();
+    Ok(outcomes)
+}
+
+pub(crate) async fn delete_many<T: Entity>(
+    ctx: &Context,
+    conditions: Document,
+) -> Result<BulkReport> {
+    let mut items: Vec<T> =
+        T::filter(conditions).find(ctx).await?.try_collect().await?;
+
+    let mut report = BulkReport::default();
+    for batch in items.chunks_mut(BULK_BATCH_SIZE) {
+        let outcomes = delete_batch(ctx, batch).await?;
+        let items = batch.iter().zip(outcomes).map(|(item, outcome)| {
+            let id = item.object_id();
+            BulkItem { id, outcome }
+        });
+        report.items.extend(items);
+    }
+    Ok(report)
+}
+
+async fn delete_batch<T: Entity>(
+    ctx: &Context,
+    items: &mut [T],
+) -> Result<Vec<BulkOutcome>> {
+    let mut outcomes: Vec<Option<BulkOutcome>> = vec![None; items.len()];
+
+    // Run pre-delete hooks.
+    for (item, outcome) in items.iter_mut().zip(&mut outcomes) {
+        if let Err(error) = item.before_delete(ctx).await {
+            *outcome = Some(failed(error));
+        }
+    }
+
+    // Delete every item that is still pending.
+    let mut indices = Vec::new();
+    let mut statements = Vec::new();
+    for (i, item) in items.iter().enumerate() {
+        if outcomes[i].is_some() {
+            continue;
+        }
+        let id = item.object_id();
+        indices.push(i);
+        statements.push(doc! { "q": { "_id": id }, "limit": 1 });
+    }
+    let result = write::<T>(ctx, "delete", "deletes", statements).await?;
+    for (n, i) in indices.into_iter().enumerate() {
+        let outcome = match result.errors.get(&n) {
+            Some(message) => BulkOutcome::Failed(message.to_owned()),
+            None => BulkOutcome::Deleted,
+        };
+        outcomes[i] = Some(outcome);
+    }
+    invalidate(ctx, items, &outcomes);
+
+    // Run post-delete hooks for items that were deleted.
+    for (item, outcome) in items.iter_mut().zip(&mut outcomes) {
+        if let Some(BulkOutcome::Failed(_)) = outcome {
+            continue;
+        }
+        if let Err(error) = item.after_delete(ctx).await {
+            *outcome = Some(failed(error));
+        }
+    }
+
+    // Publish events for items that were deleted.
+    if ctx.events.has_subscribers::<T>() {
+        for (item, outcome) in items.iter().zip(&outcomes) {
+            if let Some(BulkOutcome::Failed(_)) | None = outcome {
+                continue;
+            }
+            let id = item.global_id();
+            let old = item.clone();
+            let event = EntityEvent::Deleted { id, old };
+            ctx.events.publish(event).await;
+        }
+    }
+
+    let outcomes = outcomes
+        .into_iter()
+        .map(|outcome| outcome.expect("missing bulk outcome"))
+        .collect();
+    Ok(outcomes)
+}
+
+fn invalidate<T: Entity>(
+    ctx: &Context,
+    items: &[T],
+    outcomes: &[Option<BulkOutcome>],
+) {
+    let cache = match &ctx.cache {
+        Some(cache) => cache,
+        None => return,
+    };
+    for (item, outcome) in items.iter().zip(outcomes) {
+        if let Some(BulkOutcome::Failed(_)) | None = outcome {
+            continue;
+        }
+        cache.invalidate::<T>(&item.object_id());
+    }
+}
+
+fn failed(error: Error) -> BulkOutcome {
+    BulkOutcome::Failed(format!("{:#}", error))
+}
+
+fn key_filter(doc: &Document, fields: &[&str]) -> Result<Document> {
+    let mut filter = Document::new();
+    for field in fields {
+        let value = doc.get(field).ok_or_else(|| {
+            let message = format!("missing natural key field `{}`", field);
+            Error::validation(message)
+        })?;
+        filter.insert(*field, value.clone());
+    }
+    Ok(filter)
+}
+
+async fn find_existing<T: Entity>(
+    ctx: &Context,
+    filters: Vec<Document>,
+) -> Result<Map<String, Document>> {
+    let mut existing = Map::new();
+    let fields: Vec<String> = match filters.first() {
+        Some(filter) => filter.keys().cloned().collect(),
+        None => return Ok(existing),
+    };
+    let fields: Vec<&str> = fields.iter().map(String::as_str).collect();
+
+    let collection = T::collection(ctx);
+    let mut cursor = collection.find(doc! { "$or": filters }, None).await?;
+    while let Some(doc) = cursor.next().await {
+        let doc = doc?;
+        let key = match key_filter(&doc, &fields) {
+            Ok(key) => key,
+            Err(_) => continue,
+        };
+        existing.insert(key.to_string(), doc);
+    }
+    Ok(existing)
+}
+
+struct WriteResult {
+    upserted: Vec<usize>,
+    errors: Map<usize, String>,
+}
+
+async fn write<T: Entity>(
+    ctx: &Context,
+    command: &str,
+    statements_field: &str,
+    statements: Vec<Document>,
+) -> Result<WriteResult> {
+    let mut result = WriteResult {
+        upserted: Vec::new(),
+        errors: Map::new(),
+    };
+    if statements.is_empty() {
+        return Ok(result);
+    }
+
+    let mut cmd = Document::new();
+    cmd.insert(command, T::COLLECTION_NAME);
+    cmd.insert(statements_field, statements);
+    cmd.insert("ordered", false);
+    let reply = ctx.database.run_command(cmd, None).await?;
+
+    if let Ok(error) = reply.get_document("writeConcernError") {
+        let message = error.get_str("errmsg").unwrap_or("unknown error");
+        let message = format!("write concern error: {}", message);
+        return Err(Error::internal(message));
+    }
+    if let Ok(upserted) = reply.get_array("upserted") {
+        for entry in upserted {
+            if let Some(index) = entry.as_document().and_then(write_index) {
+                result.upserted.push(index);
+            }
+        }
+    }
+    if let Ok(errors) = reply.get_array("writeErrors") {
+        for entry in errors {
+            let entry = match entry.as_document() {
+                Some(entry) => entry,
+                None => continue,
+            };
+            if let Some(index) = write_index(entry) {
+                let message = entry.get_str("errmsg").unwrap_or("write failed");
+                result.errors.insert(index, message.to_owned());
+            }
+        }
+    }
+    Ok(result)
+}
+
+fn write_index(entry: &Document) -> Option<usize> {
+    let index = entry.get("index")?;
+    let index = match index.as_i32() {
+        Some(index) => index.into(),
+        None => index.as_i64()?,
+    };
+    index.try_into().ok()
+}
@@ -0,0 +1,122 @@
+use super::prelude::*;
+
+use lru::LruCache;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// An in-process, read-through cache of entity documents, keyed by their
+/// `_id` or natural keys.
+pub struct EntityCache {
+    ttl: StdDuration,
+    entries: Mutex<LruCache<String, CacheEntry>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+struct CacheEntry {
+    collection: &'static str,
+    id: ObjectId,
+    doc: Document,
+    expires_at: Instant,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
+
+impl EntityCache {
+    pub fn new(capacity: usize, ttl: StdDuration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(LruCache::new(capacity)),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let entries = self.entries.lock().unwrap().len();
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries,
+        }
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+impl EntityCache {
+    /// Returns the cache key for a query, if its conditions select a single
+    /// entity by `_id` or by one of its natural keys.
+    pub(crate) fn key<T: Entity>(conditions: &Document) -> Option<String> {
+        if conditions.len() != 1 {
+            return None;
+        }
+        let (field, _) = conditions.iter().next()?;
+        if field != "_id" && !T::NATURAL_KEYS.contains(&field.as_str()) {
+            return None;
+        }
+        let key = format!("{}:{}", T::COLLECTION_NAME, conditions);
+        Some(key)
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<Document> {
+        let key = key.to_owned();
+        let doc = {
+            let mut entries = self.entries.lock().unwrap();
+            match entries.get(&key) {
+                Some(entry) if entry.expires_at > Instant::now() => {
+                    Some(entry.doc.clone())
+                }
+                Some(_) => {
+                    entries.pop(&key);
+                    None
+                }
+                None => None,
+            }
+        };
+        let counter = match &doc {
+            Some(_) => &self.hits,
+            None => &self.misses,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        doc
+    }
+
+    pub(crate) fn insert<T: Entity>(
+        &self,
+        key: String,
+        id: ObjectId,
+        doc: Document,
+    ) {
+        let entry = CacheEntry {
+            collection: T::COLLECTION_NAME,
+            id,
+            doc,
+            expires_at: Instant::now() + self.ttl,
+        };
+        self.entries.lock().unwrap().put(key, entry);
+    }
+
+    /// Removes every entry that resolved to the given entity.
+    pub(crate) fn invalidate<T: Entity>(&self, id: &ObjectId) {
+        let mut entries = self.entries.lock().unwrap();
+        let keys: Vec<String> = entries
+            .iter()
+            .filter(|(_, entry)| {
+                entry.collection == T::COLLECTION_NAME && &entry.id == id
+            })
+            .map(|(key, _)| key.to_owned())
+            .collect();
+        for key in keys {
+            entries.pop(&key);
+        }
+    }
+}
@@ -10,3 +10,36 @@ pub(crate) fn from_date_time(date_time: DateTime) -> Date {
     let date = date_time.naive_utc().date();
     date.into()
 }
+
+/// Calls `f` with the value of the field at `path` in `doc`, if it's set.
+///
+/// `path` may be a dotted path through nested documents, or through arrays
+/// of documents, in which case `f` is called for each document's field.
+pub(crate) fn visit_field(
+    doc: &mut Document,
+    path: &str,
+    f: &mut dyn FnMut(&mut Bson) -> Result<()>,
+) -> Result<()> {
+    let mut parts = path.splitn(2, '.');
+    let field = parts.next().unwrap_or(path);
+    let value = match doc.get_mut(field) {
+        Some(value) => value,
+        None => return Ok(()),
+    };
+    let rest = match parts.next() {
+        Some(rest) => rest,
+        None => return f(value),
+    };
+    match value {
+        Bson::Document(doc) => visit_field(doc, rest, f),
+        Bson::Array(items) => {
+            for item in items {
+                if let Bson::Document(doc) = item {
+                    visit_field(doc, rest, f)?;
+                }
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
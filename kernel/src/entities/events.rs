@@ -0,0 +1,87 @@
+use super::prelude::*;
+
+use futures::future::{join_all, BoxFuture};
+use std::any::Any;
+use std::sync::RwLock;
+
+#[derive(Debug, Clone)]
+pub enum EntityEvent<T: Entity> {
+    Created { id: GlobalId, new: T },
+    Updated { id: GlobalId, old: T, new: T },
+    Deleted { id: GlobalId, old: T },
+}
+
+impl<T: Entity> EntityEvent<T> {
+    pub fn id(&self) -> &GlobalId {
+        use EntityEvent::*;
+        match self {
+            Created { id, .. } => id,
+            Updated { id, .. } => id,
+            Deleted { id, .. } => id,
+        }
+    }
+}
+
+type Subscriber =
+    Arc<dyn Fn(&dyn Any) -> BoxFuture<'static, Result<()>> + Send + Sync>;
+
+/// An in-process bus that publishes entity changes to subscribers.
+#[derive(Default)]
+pub struct EventBus {
+    subscribers: RwLock<Map<ObjectType, Vec<Subscriber>>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a handler that is called whenever an entity of type `T` is
+    /// created, updated, or deleted.
+    pub fn subscribe<T, F, Fut>(&self, handler: F)
+    where
+        T: Entity,
+        F: Fn(EntityEvent<T>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let subscriber: Subscriber =
+            Arc::new(move |event: &dyn Any| {
+                match event.downcast_ref::<EntityEvent<T>>() {
+                    Some(event) => handler(event.clone()).boxed(),
+                    None => async { Ok(()) }.boxed(),
+                }
+            });
+        let mut subscribers = self.subscribers.write().unwrap();
+        subscribers
+            .entry(T::OBJECT_TYPE)
+            .or_default()
+            .push(subscriber);
+    }
+
+    pub fn has_subscribers<T: Entity>(&self) -> bool {
+        let subscribers = self.subscribers.read().unwrap();
+        subscribers.contains_key(&T::OBJECT_TYPE)
+    }
+
+    /// Publishes an event to all subscribers, and waits for them to handle
+    /// it. Subscriber errors are logged, and don't fail the publisher.
+    pub async fn publish<T: Entity>(&self, event: EntityEvent<T>) {
+        let subscribers: Vec<Subscriber> = {
+            let subscribers = self.subscribers.read().unwrap();
+            match subscribers.get(&T::OBJECT_TYPE) {
+                Some(subscribers) => subscribers.clone(),
+                None => return,
+            }
+        };
+        let id = event.id().to_owned();
+        let handlers = subscribers
+            .iter()
+            .map(|subscriber| subscriber(&event))
+            .collect::<Vec<_>>();
+        for result in join_all(handlers).await {
+            if let Err(error) = result {
+                warn!(%id, "entity event subscriber failed: {:#}", error);
+            }
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use super::prelude::*;
+
+/// An account with an identity provider, which a user signs in with.
+///
+/// Users are identified by their provider and subject, which are stable,
+/// rather than by email, which can change or be reassigned. A user may link
+/// one identity per provider.
+#[entity_fields]
+#[derive(Debug, Clone, Serialize, Deserialize, Builder, Entity)]
+#[builder(field_defaults(setter(into)))]
+pub struct IdentityLink {
+    pub user: ObjectRef,
+
+    /// The provider's token issuer, i.e.
+    /// "https://securetoken.google.com/lattice".
+    pub provider: String,
+
+    /// The user's ID with the provider (the token's `sub` claim).
+    pub subject: String,
+
+    /// The email the provider reported when the identity was linked.
+    pub email: String,
+}
+
+impl IdentityLink {
+    pub fn find_by_subject(
+        provider: impl Into<String>,
+        subject: impl Into<String>,
+    ) -> FindOneQuery<Self> {
+        let provider: String = provider.into();
+        let subject: String = subject.into();
+        Self::find_by(doc! { "provider": provider, "subject": subject })
+    }
+
+    pub fn user(&self) -> FindOneQuery<User> {
+        User::find(&self.user.id)
+    }
+}
+
+#[async_trait]
+impl Entity for IdentityLink {
+    const COLLECTION_NAME: &'static str = "identity_links";
+
+    async fn before_save(&mut self, ctx: &Context) -> Result<()> {
+        if self.provider.is_empty() || self.subject.is_empty() {
+            let error =
+                Error::validation("identity must have a provider and subject");
+            return Err(error);
+        }
+        self.email = self.email.to_lowercase();
+
+        // Check that the user exists.
+        if !self.user().exists(ctx).await? {
+            return Err(Error::not_found("user"));
+        }
+
+        // Check that the user hasn't linked another identity with the same
+        // provider.
+        let conditions = doc! {
+            "_id": { "$ne": &self.id },
+            "user.id": &self.user.id,
+            "provider": &self.provider,
+        };
+        if Self::filter(conditions).count(ctx).await? > 0 {
+            let error = Error::conflict(
+                "user already has an identity with this provider",
+            );
+            return Err(error);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Builder)]
+#[builder(field_defaults(default, setter(into)))]
+pub struct IdentityLinkConditions {
+    pub user: Option<ObjectRef>,
+    pub provider: Option<String>,
+}
+
+impl From<IdentityLinkConditions> for Document {
+    fn from(conditions: IdentityLinkConditions) -> Document {
+        let mut doc = Document::new();
+
+        let IdentityLinkConditions { user, provider } = conditions;
+        if let Some(user_ref) = user {
+            doc.insert("user.id", user_ref.id);
+        }
+        if let Some(provider) = provider {
+            doc.insert("provider", provider);
+        }
+
+        doc
+    }
+}
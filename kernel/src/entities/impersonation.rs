@@ -0,0 +1,98 @@
+use super::prelude::*;
+
+/// A session in which an admin viewed the API as another user.
+///
+/// Consecutive requests by an admin as the same user belong to one session,
+/// until it has been idle for `IDLE_TIMEOUT_MINUTES`. A session's
+/// `updated_at` is the time of its latest request.
+#[entity_fields]
+#[derive(Debug, Clone, Serialize, Deserialize, Builder, Entity)]
+#[builder(field_defaults(setter(into)))]
+pub struct Impersonation {
+    /// The admin who impersonated the target.
+    pub admin: ObjectRef,
+
+    /// The user who was impersonated.
+    pub target: ObjectRef,
+
+    #[builder(default, setter(skip))]
+    pub request_count: u32,
+}
+
+impl Impersonation {
+    pub const IDLE_TIMEOUT_MINUTES: i64 = 30;
+
+    /// Records a request by `admin` as `target`, continuing their current
+    /// session if there is one.
+    pub async fn record(
+        ctx: &Context,
+        admin: &User,
+        target: &User,
+    ) -> Result<Self> {
+        if !admin.is_admin {
+            let error = Error::validation("only admins may impersonate users");
+            return Err(error);
+        }
+        if admin.id == target.id {
+            let error =
+                Error::validation("admins can't impersonate themselves");
+            return Err(error);
+        }
+
+        let now = Utc::now();
+        let idle_since = now - Duration::minutes(Self::IDLE_TIMEOUT_MINUTES);
+        let conditions = doc! {
+            "admin.id": &admin.id,
+            "target.id": &target.id,
+            "updated_at": { "$gte": idle_since },
+        };
+        let session = Self::find_by(conditions).load(ctx).await?;
+        let mut session = match session {
+            Some(session) => session,
+            None => Self::builder()
+                .admin(admin.object_ref())
+                .target(target.object_ref())
+                .build(),
+        };
+        session.updated_at = now;
+        session.request_count += 1;
+        session.save(ctx).await?;
+        Ok(session)
+    }
+
+    pub fn admin(&self) -> FindOneQuery<User> {
+        User::find(&self.admin.id)
+    }
+
+    pub fn target(&self) -> FindOneQuery<User> {
+        User::find(&self.target.id)
+    }
+}
+
+#[async_trait]
+impl Entity for Impersonation {
+    const COLLECTION_NAME: &'static str = "impersonations";
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Builder)]
+#[builder(field_defaults(default, setter(into)))]
+pub struct ImpersonationConditions {
+    pub admin: Option<ObjectRef>,
+    pub target: Option<ObjectRef>,
+}
+
+impl From<ImpersonationConditions> for Document {
+    fn from(conditions: ImpersonationConditions) -> Document {
+        let mut doc = Document::new();
+
+        let ImpersonationConditions { admin, target } = conditions;
+        if let Some(admin_ref) = admin {
+            doc.insert("admin.id", admin_ref.id);
+        }
+        if let Some(target_ref) = target {
+            doc.insert("target.id", target_ref.id);
+        }
+
+        doc
+    }
+}
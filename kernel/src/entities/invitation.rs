@@ -0,0 +1,328 @@
+use super::prelude::*;
+
+use crate::secrets;
+
+#[derive(
+    Debug,
+    Display,
+    Clone,
+    Copy,
+    Hash,
+    PartialEq,
+    Eq,
+    EnumString,
+    Serialize,
+    Deserialize,
+)]
+pub enum InvitationStatus {
+    Pending,
+    Accepted,
+    Revoked,
+    Expired,
+}
+
+/// An invitation for someone to join with a member role, which is accepted
+/// when they register with the invitation's token.
+///
+/// Only a hash of the token is stored; the token itself is shown once, when
+/// the invitation is created.
+#[entity_fields]
+#[derive(Debug, Clone, Serialize, Deserialize, Builder, Entity)]
+#[builder(field_defaults(setter(into)))]
+pub struct Invitation {
+    pub email: String,
+    pub role: ObjectRef,
+    pub term: ObjectRef,
+    pub inviter: ObjectRef,
+
+    #[entity(date_time)]
+    pub expires_at: DateTime,
+
+    pub token_hash: String,
+
+    #[builder(default, setter(skip))]
+    pub revoked_at: Option<DateTime>,
+
+    #[builder(default, setter(skip))]
+    pub accepted_at: Option<DateTime>,
+
+    #[builder(default, setter(skip))]
+    pub accepted_by: Option<ObjectRef>,
+}
+
+impl Invitation {
+    /// How long an invitation lasts if no expiry is given.
+    pub const DEFAULT_TTL_DAYS: i64 = 14;
+
+    /// Generates a random token for a new invitation.
+    pub fn generate_token() -> String {
+        secrets::generate()
+    }
+
+    pub fn hash_token(token: &str) -> String {
+        secrets::hash(token)
+    }
+
+    pub fn find_by_token(token: &str) -> FindOneQuery<Self> {
+        let token_hash = Self::hash_token(token);
+        Self::find_by(doc! { "token_hash": token_hash })
+    }
+
+    pub fn role(&self) -> FindOneQuery<MemberRole> {
+        MemberRole::find(&self.role.id)
+    }
+
+    pub fn term(&self) -> FindOneQuery<Term> {
+        Term::find(&self.term.id)
+    }
+
+    pub fn inviter(&self) -> FindOneQuery<User> {
+        User::find(&self.inviter.id)
+    }
+
+    pub fn status(&self) -> InvitationStatus {
+        use InvitationStatus::*;
+        if self.accepted_at.is_some() {
+            Accepted
+        } else if self.revoked_at.is_some() {
+            Revoked
+        } else if self.expires_at <= Utc::now() {
+            Expired
+        } else {
+            Pending
+        }
+    }
+
+    /// Revokes the invitation, unless it was accepted or revoked since it
+    /// was loaded.
+    pub async fn revoke(&mut self, ctx: &Context) -> Result<()> {
+        if self.status() != InvitationStatus::Pending {
+            let error = Error::conflict("invitation is not pending");
+            return Err(error);
+        }
+
+        let revoked_at = Utc::now();
+        let conditions = doc! {
+            "_id": &self.id,
+            "accepted_at": Bson::Null,
+            "revoked_at": Bson::Null,
+        };
+        let update = doc! {
+            "$set": { "revoked_at": to_bson(&revoked_at)? },
+        };
+        let result = Self::collection(ctx)
+            .update_one(conditions, update, None)
+            .await?;
+        self.invalidate(ctx);
+        if result.matched_count == 0 {
+            let error = Error::conflict("invitation is no longer pending");
+            return Err(error);
+        }
+        self.revoked_at = Some(revoked_at);
+        Ok(())
+    }
+
+    /// Accepts the invitation on behalf of `user`, creating their membership
+    /// for the invitation's role and the rest of its term.
+    ///
+    /// The invitation is claimed before the membership is created, so that
+    /// it can only be accepted once. `user` needn't have been saved yet.
+    pub async fn accept(
+        &mut self,
+        ctx: &Context,
+        user: &User,
+    ) -> Result<Membership> {
+        let status = self.status();
+        if status != InvitationStatus::Pending {
+            let message =
+                format!("invitation is {}", status.to_string().to_lowercase());
+            return Err(Error::conflict(message));
+        }
+        if !self.email.eq_ignore_ascii_case(&user.email) {
+            let error =
+                Error::validation("invitation is for a different email");
+            return Err(error);
+        }
+
+        let term = self
+            .term()
+            .load(ctx)
+            .await?
+            .ok_or_else(|| Error::not_found("term"))?;
+        let today = Utc::today().naive_utc();
+        if term.end < today {
+            let error = Error::validation("invitation's term has ended");
+            return Err(error);
+        }
+
+        // Claim the invitation, unless it was accepted or revoked since it
+        // was loaded.
+        let accepted_at = Utc::now();
+        let accepted_by = user.object_ref();
+        let conditions = doc! {
+            "_id": &self.id,
+            "accepted_at": Bson::Null,
+            "revoked_at": Bson::Null,
+        };
+        let update = doc! {
+            "$set": {
+                "accepted_at": to_bson(&accepted_at)?,
+                "accepted_by": to_bson(&accepted_by)?,
+            },
+        };
+        let claimed = Self::collection(ctx)
+            .find_one_and_update(conditions, update, None)
+            .await?;
+        self.invalidate(ctx);
+        if claimed.is_none() {
+            let error = Error::conflict("invitation is no longer pending");
+            return Err(error);
+        }
+        self.accepted_at = Some(accepted_at);
+        self.accepted_by = Some(accepted_by);
+
+        let mut membership = Membership::builder()
+            .user(user.object_ref())
+            .role(self.role.clone())
+            .term(self.term.clone())
+            .start(term.start.max(today))
+            .end(term.end)
+            .build();
+        if let Err(error) = membership.save(ctx).await {
+            self.release(ctx).await?;
+            return Err(error);
+        }
+        Ok(membership)
+    }
+
+    /// Undoes accepting the invitation, deleting the `membership` that was
+    /// created for it, i.e. if the invitee couldn't be saved.
+    pub async fn rollback_accept(
+        &mut self,
+        ctx: &Context,
+        mut membership: Membership,
+    ) -> Result<()> {
+        membership.delete(ctx).await?;
+        self.release(ctx).await
+    }
+
+    /// Marks a claimed invitation as pending again.
+    async fn release(&mut self, ctx: &Context) -> Result<()> {
+        let conditions = doc! { "_id": &self.id };
+        let update = doc! {
+            "$set": { "accepted_at": Bson::Null, "accepted_by": Bson::Null },
+        };
+        Self::collection(ctx)
+            .update_one(conditions, update, None)
+            .await?;
+        self.invalidate(ctx);
+        self.accepted_at = None;
+        self.accepted_by = None;
+        Ok(())
+    }
+
+    fn invalidate(&self, ctx: &Context) {
+        if let Some(cache) = ctx.cache() {
+            cache.invalidate::<Self>(&self.id);
+        }
+    }
+}
+
+#[async_trait]
+impl Entity for Invitation {
+    const COLLECTION_NAME: &'static str = "invitations";
+
+    async fn before_save(&mut self, ctx: &Context) -> Result<()> {
+        self.email = self.email.trim().to_lowercase();
+        if !self.email.contains('@') {
+            let error = Error::validation("invalid email");
+            return Err(error);
+        }
+
+        // Check that the invitation's role and term exist.
+        if !self.role().exists(ctx).await? {
+            return Err(Error::not_found("member role"));
+        }
+        if !self.term().exists(ctx).await? {
+            return Err(Error::not_found("term"));
+        }
+
+        // Check that there isn't already a pending invitation for the same
+        // email, role, and term.
+        if self.status() == InvitationStatus::Pending {
+            let conditions = InvitationConditions::builder()
+                .email(self.email.clone())
+                .role(self.role.clone())
+                .term(self.term.clone())
+                .open(true)
+                .build();
+            let mut conditions = Document::from(conditions);
+            conditions.insert("_id", doc! { "$ne": &self.id });
+            if Invitation::find_by(conditions).exists(ctx).await? {
+                let error = Error::conflict(
+                    "a pending invitation already exists for this email",
+                );
+                return Err(error);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Builder)]
+#[builder(field_defaults(default, setter(into)))]
+pub struct InvitationConditions {
+    pub email: Option<String>,
+    pub role: Option<ObjectRef>,
+    pub term: Option<ObjectRef>,
+    pub inviter: Option<ObjectRef>,
+
+    /// Invitations that are (or aren't) pending, i.e. that have been neither
+    /// accepted nor revoked, and haven't expired.
+    pub open: Option<bool>,
+}
+
+impl From<InvitationConditions> for Document {
+    fn from(conditions: InvitationConditions) -> Document {
+        let mut doc = Document::new();
+
+        let InvitationConditions {
+            email,
+            role,
+            term,
+            inviter,
+            open,
+        } = conditions;
+        if let Some(email) = email {
+            doc.insert("email", email.trim().to_lowercase());
+        }
+        if let Some(role_ref) = role {
+            doc.insert("role.id", role_ref.id);
+        }
+        if let Some(term_ref) = term {
+            doc.insert("term.id", term_ref.id);
+        }
+        if let Some(inviter_ref) = inviter {
+            doc.insert("inviter.id", inviter_ref.id);
+        }
+        let now = Bson::DateTime(Utc::now());
+        match open {
+            Some(true) => {
+                doc.insert("accepted_at", Bson::Null);
+                doc.insert("revoked_at", Bson::Null);
+                doc.insert("expires_at", doc! { "$gt": now });
+            }
+            Some(false) => {
+                let clauses = vec![
+                    doc! { "accepted_at": { "$ne": Bson::Null } },
+                    doc! { "revoked_at": { "$ne": Bson::Null } },
+                    doc! { "expires_at": { "$lte": now } },
+                ];
+                doc.insert("$or", clauses);
+            }
+            None => {}
+        }
+
+        doc
+    }
+}
@@ -1,114 +1,129 @@
 use super::prelude::*;
 
-#[derive(Debug, Clone, Serialize, Deserialize, Builder)]
+use std::collections::HashSet;
+
+#[entity_fields]
+#[derive(Debug, Clone, Serialize, Deserialize, Builder, Entity)]
 #[builder(field_defaults(setter(into)))]
 pub struct Membership {
-    #[builder(default, setter(skip))]
-    pub id: ObjectId,
-
-    #[builder(default = Utc::now(), setter(skip))]
-    pub created_at: DateTime,
-
-    #[builder(default = Utc::now(), setter(skip))]
-    pub updated_at: DateTime,
-
     pub user: ObjectRef,
     pub role: ObjectRef,
 
+    #[entity(date)]
     pub start: Date,
+
+    #[entity(date)]
     pub end: Date,
-}
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct MembershipDocument {
-    #[serde(rename = "_id")]
-    pub id: ObjectId,
-    pub created_at: BsonDateTime,
-    pub updated_at: BsonDateTime,
-    pub user: ObjectRef,
-    pub role: ObjectRef,
-    pub start: BsonDateTime,
-    pub end: BsonDateTime,
+    #[builder(default)]
+    pub term: Option<ObjectRef>,
 }
 
-impl From<MembershipDocument> for Membership {
-    fn from(doc: MembershipDocument) -> Self {
-        let MembershipDocument {
-            id,
-            created_at,
-            updated_at,
-            user,
-            role,
-            start,
-            end,
-        } = doc;
-
-        Membership {
-            id,
-            created_at: created_at.into(),
-            updated_at: updated_at.into(),
-            user,
-            role,
-            start: date::from_date_time(start.into()),
-            end: date::from_date_time(end.into()),
-        }
+impl Membership {
+    pub fn user(&self) -> FindOneQuery<User> {
+        User::find(&self.user.id)
     }
-}
 
-impl From<Membership> for MembershipDocument {
-    fn from(membership: Membership) -> Self {
-        let Membership {
-            id,
-            created_at,
-            updated_at,
-            user,
-            role,
-            start,
-            end,
-        } = membership;
-
-        MembershipDocument {
-            id,
-            created_at: created_at.into(),
-            updated_at: updated_at.into(),
-            user,
-            role,
-            start: date::to_date_time(start).into(),
-            end: date::to_date_time(end).into(),
-        }
+    pub fn role(&self) -> FindOneQuery<MemberRole> {
+        MemberRole::find(&self.role.id)
     }
-}
-
-impl Object for Membership {
-    const OBJECT_TYPE: ObjectType = ObjectType::Membership;
 
-    fn object_id(&self) -> ObjectId {
-        self.id.clone()
+    pub fn range(&self) -> DateRange {
+        DateRange::new(self.start, self.end)
     }
 
-    fn to_document(&self) -> Result<Document> {
-        let doc = MembershipDocument::from(self.clone());
-        let doc = to_document(&doc)?;
-        Ok(doc)
+    pub fn term(&self) -> Option<FindOneQuery<Term>> {
+        self.term.as_ref().map(|term_ref| Term::find(&term_ref.id))
     }
 
-    fn from_document(doc: Document) -> Result<Self> {
-        let doc: MembershipDocument = from_document(doc)?;
-        Ok(doc.into())
+    /// Finds the terms that the membership spans, in order.
+    pub fn terms(&self) -> FindQuery<Term> {
+        let conditions =
+            TermConditions::builder().overlapping(self.range()).build();
+        Term::filter(conditions).sort(TermSorting::Start(SortingOrder::Asc))
     }
 }
 
+#[async_trait]
 impl Entity for Membership {
     const COLLECTION_NAME: &'static str = "memberships";
+
+    async fn before_save(&mut self, ctx: &Context) -> Result<()> {
+        // Check that the membership doesn't end before it starts.
+        if self.end < self.start {
+            let error =
+                Error::validation("membership must not end before it starts");
+            return Err(error);
+        }
+
+        // Check that the membership's term exists.
+        if let Some(term) = self.term() {
+            if !term.exists(ctx).await? {
+                return Err(Error::not_found("term"));
+            }
+        }
+
+        // Check that the membership is no longer than its role allows.
+        let role = self
+            .role()
+            .load(ctx)
+            .await?
+            .ok_or_else(|| Error::not_found("member role"))?;
+        if let Some(max_days) = role.max_duration_days {
+            let days = self.range().days();
+            if days > i64::from(max_days) {
+                let message = format!(
+                    "membership must not last longer than {} days",
+                    max_days
+                );
+                return Err(Error::validation(message));
+            }
+        }
+
+        // Check that the membership doesn't overlap with another membership
+        // of the same user and role.
+        let conditions = MembershipConditions::builder()
+            .user(self.user.clone())
+            .role(self.role.clone())
+            .overlapping(self.range())
+            .build();
+        let mut conditions = Document::from(conditions);
+        conditions.insert("_id", doc! { "$ne": &self.id });
+        let count = Membership::filter(conditions).count(ctx).await?;
+        if count.is_positive() {
+            let error = Error::conflict(
+                "membership overlaps an existing membership for this role",
+            );
+            return Err(error);
+        }
+
+        Ok(())
+    }
 }
 
-impl Membership {
-    pub fn user(&self) -> FindOneQuery<User> {
-        User::find(&self.user.id)
+/// An inclusive range of dates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DateRange {
+    pub start: Date,
+    pub end: Date,
+}
+
+impl DateRange {
+    pub fn new(start: Date, end: Date) -> Self {
+        Self { start, end }
     }
 
-    pub fn role(&self) -> FindOneQuery<MemberRole> {
-        MemberRole::find(&self.role.id)
+    pub fn contains(&self, date: Date) -> bool {
+        self.start <= date && date <= self.end
+    }
+
+    pub fn overlaps(&self, other: &DateRange) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+
+    /// The number of days in the range, including both ends.
+    pub fn days(&self) -> i64 {
+        (self.end - self.start).num_days() + 1
     }
 }
 
@@ -117,19 +132,66 @@ impl Membership {
 pub struct MembershipConditions {
     pub user: Option<ObjectRef>,
     pub role: Option<ObjectRef>,
+    pub roles: Option<Vec<ObjectRef>>,
+
+    /// Memberships that cover the given date.
+    pub active_at: Option<Date>,
+
+    /// Memberships that share at least one day with the given range.
+    pub overlapping: Option<DateRange>,
+
+    /// Memberships that ended before the given date.
+    pub ended_before: Option<Date>,
 }
 
 impl From<MembershipConditions> for Document {
     fn from(conditions: MembershipConditions) -> Document {
         let mut doc = Document::new();
 
-        let MembershipConditions { user, role } = conditions;
+        let MembershipConditions {
+            user,
+            role,
+            roles,
+            active_at,
+            overlapping,
+            ended_before,
+        } = conditions;
         if let Some(user_ref) = user {
             doc.insert("user.id", user_ref.id);
         }
         if let Some(role_ref) = role {
             doc.insert("role.id", role_ref.id);
         }
+        if let Some(roles) = roles {
+            let ids: Vec<ObjectId> =
+                roles.into_iter().map(|role_ref| role_ref.id).collect();
+            doc.insert("role.id", doc! { "$in": ids });
+        }
+
+        // Date conditions may constrain the same fields, so they are
+        // combined with `$and`.
+        let mut clauses: Vec<Document> = Vec::new();
+        if let Some(date) = active_at {
+            let date = date::to_date_time(date);
+            clauses.push(doc! {
+                "start": { "$lte": date },
+                "end": { "$gte": date },
+            });
+        }
+        if let Some(range) = overlapping {
+            let DateRange { start, end } = range;
+            clauses.push(doc! {
+                "start": { "$lte": date::to_date_time(end) },
+                "end": { "$gte": date::to_date_time(start) },
+            });
+        }
+        if let Some(date) = ended_before {
+            let date = date::to_date_time(date);
+            clauses.push(doc! { "end": { "$lt": date } });
+        }
+        if !clauses.is_empty() {
+            doc.insert("$and", clauses);
+        }
 
         doc
     }
@@ -151,43 +213,151 @@ impl From<MembershipSorting> for Document {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Builder)]
+#[entity_fields]
+#[derive(Debug, Clone, Serialize, Deserialize, Builder, Entity)]
 #[builder(field_defaults(setter(into)))]
 pub struct MemberRole {
-    #[builder(default, setter(skip))]
-    pub id: ObjectId,
+    #[entity(search)]
+    pub name: String,
 
-    #[builder(default = Utc::now(), setter(skip))]
-    pub created_at: DateTime,
+    #[entity(search)]
+    pub description: String,
 
-    #[builder(default = Utc::now(), setter(skip))]
-    pub updated_at: DateTime,
+    /// The longest a membership with this role may last, in days.
+    #[builder(default)]
+    pub max_duration_days: Option<u32>,
 
-    pub name: String,
-    pub description: String,
+    #[builder(default)]
+    pub team: Option<ObjectRef>,
+
+    /// The role that this role reports to.
+    #[builder(default)]
+    pub reports_to: Option<ObjectRef>,
 }
 
-// TODO: Don't allow deleting a member role that users are bound to.
-impl Object for MemberRole {
-    const OBJECT_TYPE: ObjectType = ObjectType::MemberRole;
+impl MemberRole {
+    pub fn team(&self) -> Option<FindOneQuery<Team>> {
+        self.team.as_ref().map(|team_ref| Team::find(&team_ref.id))
+    }
+
+    pub fn reports_to(&self) -> Option<FindOneQuery<MemberRole>> {
+        self.reports_to
+            .as_ref()
+            .map(|role_ref| MemberRole::find(&role_ref.id))
+    }
 
-    fn object_id(&self) -> ObjectId {
-        self.id.clone()
+    pub fn direct_reports(&self) -> FindQuery<MemberRole> {
+        let role_ref = self.object_ref();
+        let conditions =
+            MemberRoleConditions::builder().reports_to(role_ref).build();
+        MemberRole::filter(conditions)
+    }
+
+    /// Returns whether this role is one of `role_ids`, or reports (directly
+    /// or indirectly) to one of them.
+    pub async fn is_within(
+        &self,
+        ctx: &Context,
+        role_ids: &[ObjectId],
+    ) -> Result<bool> {
+        if role_ids.contains(&self.id) {
+            return Ok(true);
+        }
+        let mut visited = HashSet::new();
+        visited.insert(self.id.clone());
+        let mut manager_ref = self.reports_to.clone();
+        while let Some(ObjectRef { id, .. }) = manager_ref {
+            if role_ids.contains(&id) {
+                return Ok(true);
+            }
+            if !visited.insert(id.clone()) {
+                break;
+            }
+            let manager = match MemberRole::find(&id).load(ctx).await? {
+                Some(manager) => manager,
+                None => break,
+            };
+            manager_ref = manager.reports_to;
+        }
+        Ok(false)
     }
 }
 
+// TODO: Don't allow deleting a member role that users are bound to.
 #[async_trait]
 impl Entity for MemberRole {
     const COLLECTION_NAME: &'static str = "member_roles";
 
+    async fn before_save(&mut self, ctx: &Context) -> Result<()> {
+        // Check that the role's team exists.
+        if let Some(team) = self.team() {
+            if !team.exists(ctx).await? {
+                return Err(Error::not_found("team"));
+            }
+        }
+
+        // Check that the roles this role reports to exist, and don't include
+        // the role itself.
+        let mut visited = HashSet::new();
+        let mut manager_ref = self.reports_to.clone();
+        while let Some(ObjectRef { id, .. }) = manager_ref {
+            if id == self.id {
+                let error =
+                    Error::validation("member role must not report to itself");
+                return Err(error);
+            }
+            if !visited.insert(id.clone()) {
+                let error =
+                    Error::validation("member role's managers form a cycle");
+                return Err(error);
+            }
+            let manager = MemberRole::find(&id)
+                .load(ctx)
+                .await?
+                .ok_or_else(|| Error::not_found("member role"))?;
+            manager_ref = manager.reports_to;
+        }
+        Ok(())
+    }
+
     async fn before_delete(&mut self, ctx: &Context) -> Result<()> {
         // Check that no existing membership uses this role.
         let role_ref = self.object_ref();
         let conditions = MembershipConditions::builder().role(role_ref).build();
         let count = Membership::filter(conditions).count(ctx).await?;
         if count.is_positive() {
-            bail!("member role currently in use")
+            let error = Error::conflict("member role currently in use");
+            return Err(error);
+        }
+
+        // Check that no other role reports to this role.
+        if self.direct_reports().count(ctx).await?.is_positive() {
+            let error = Error::conflict("member role has direct reports");
+            return Err(error);
         }
         Ok(())
     }
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize, Builder)]
+#[builder(field_defaults(default, setter(into)))]
+pub struct MemberRoleConditions {
+    pub team: Option<ObjectRef>,
+    pub reports_to: Option<ObjectRef>,
+}
+
+impl From<MemberRoleConditions> for Document {
+    fn from(conditions: MemberRoleConditions) -> Document {
+        let mut doc = Document::new();
+
+        let MemberRoleConditions { team, reports_to } = conditions;
+        if let Some(team_ref) = team {
+            doc.insert("team.id", team_ref.id);
+        }
+        if let Some(role_ref) = reports_to {
+            doc.insert("reports_to.id", role_ref.id);
+        }
+
+        doc
+    }
+}
@@ -2,21 +2,61 @@ use super::prelude::*;
 
 pub use bson::oid::ObjectId;
 
-#[derive(
-    Debug,
-    Display,
-    Clone,
-    Hash,
-    PartialEq,
-    Eq,
-    EnumString,
-    Serialize,
-    Deserialize,
-)]
-pub enum ObjectType {
-    User,
-    Membership,
-    MemberRole,
+use std::borrow::Cow;
+
+/// The name of a type of object, i.e. "User".
+///
+/// Each `Object` declares its own type (see `Object::OBJECT_TYPE`), so there
+/// isn't a fixed list of them.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct ObjectType(Cow<'static, str>);
+
+impl ObjectType {
+    pub const fn new(name: &'static str) -> Self {
+        Self(Cow::Borrowed(name))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for ObjectType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ObjectType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let is_valid = !s.is_empty() && s.chars().all(char::is_alphanumeric);
+        if !is_valid {
+            let message = format!("invalid object type: {}", s);
+            return Err(Error::validation(message));
+        }
+        Ok(Self(Cow::Owned(s.to_owned())))
+    }
+}
+
+impl Serialize for ObjectType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for ObjectType {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(DeserializeError::custom)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -74,14 +114,18 @@ impl FromStr for GlobalId {
             let parts: Vec<_> = s.split(':').take(2).collect();
             match parts[..] {
                 [object_type, object_id] => [object_type, object_id],
-                _ => bail!("invalid structure"),
+                _ => return Err(Error::validation("invalid global ID")),
             }
         };
 
-        let object_type: ObjectType =
-            object_type.parse().context("failed to parse object type")?;
-        let object_id: ObjectId =
-            object_id.parse().context("failed to parse object ID")?;
+        let object_type: ObjectType = object_type.parse().map_err(|_| {
+            let message = format!("invalid object type: {}", object_type);
+            Error::validation(message)
+        })?;
+        let object_id: ObjectId = object_id.parse().map_err(|_| {
+            let message = format!("invalid object ID: {}", object_id);
+            Error::validation(message)
+        })?;
 
         let id = Self::new(object_id, object_type);
         Ok(id)
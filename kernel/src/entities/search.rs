@@ -0,0 +1,407 @@
+use super::prelude::*;
+
+use futures::future::join_all;
+
+/// The document field in which search tokens are stored.
+pub(crate) const SEARCH_TOKENS_FIELD: &str = "search_tokens";
+
+const MAX_PREFIX_LEN: usize = 12;
+const PREFIX_WEIGHT: i32 = 2;
+const TRIGRAM_WEIGHT: i32 = 1;
+
+/// Builds the search tokens for a set of field values.
+///
+/// Each word produces prefix tokens (for autocomplete) and trigram tokens
+/// (for typo tolerance).
+pub(crate) fn index_tokens<'a>(
+    values: impl IntoIterator<Item = &'a str>,
+) -> Vec<String> {
+    let mut tokens = Vec::new();
+    for value in values {
+        for word in words(value) {
+            let chars: Vec<char> = word.chars().collect();
+            let max_len = chars.len().min(MAX_PREFIX_LEN);
+            for len in 1..=max_len {
+                let prefix: String = chars[..len].iter().collect();
+                tokens.push(prefix_token(&prefix));
+            }
+            tokens.extend(trigrams(&chars).map(|gram| trigram_token(&gram)));
+        }
+    }
+    tokens.sort();
+    tokens.dedup();
+    tokens
+}
+
+fn query_tokens(query: &str) -> (Vec<String>, Vec<String>) {
+    let mut prefixes = Vec::new();
+    let mut grams = Vec::new();
+    for word in words(query) {
+        let chars: Vec<char> = word.chars().take(MAX_PREFIX_LEN).collect();
+        let prefix: String = chars.iter().collect();
+        prefixes.push(prefix_token(&prefix));
+        let chars: Vec<char> = word.chars().collect();
+        grams.extend(trigrams(&chars).map(|gram| trigram_token(&gram)));
+    }
+    prefixes.sort_unstable();
+    prefixes.dedup();
+    grams.sort();
+    grams.dedup();
+    (prefixes, grams)
+}
+
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+}
+
+fn trigrams(chars: &[char]) -> impl Iterator<Item = String> + '_ {
+    chars.windows(3).map(|window| window.iter().collect())
+}
+
+fn prefix_token(prefix: &str) -> String {
+    format!("p:{}", prefix)
+}
+
+fn trigram_token(gram: &str) -> String {
+    format!("t:{}", gram)
+}
+
+/// Reads the values of an object's search fields from its document.
+pub(crate) fn field_values<'a>(
+    doc: &'a Document,
+    fields: &'a [&'a str],
+) -> impl Iterator<Item = &'a str> {
+    fields
+        .iter()
+        .filter_map(move |field| doc.get_str(field).ok())
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchQuery<T: Entity> {
+    query: String,
+    limit: Option<u32>,
+    phantom: PhantomData<T>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchResult<T: Entity> {
+    pub entity: T,
+    pub score: i32,
+    pub highlights: Vec<SearchHighlight>,
+}
+
+/// A search field value, with the character ranges that matched the query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchHighlight {
+    pub field: String,
+    pub value: String,
+    pub ranges: Vec<(usize, usize)>,
+}
+
+impl<T: Entity> SearchQuery<T> {
+    pub fn new(query: impl Into<String>) -> Self {
+        Self {
+            query: query.into(),
+            limit: None,
+            phantom: PhantomData,
+        }
+    }
+
+    pub fn take(mut self, n: impl Into<Option<u32>>) -> Self {
+        self.limit = n.into();
+        self
+    }
+
+    pub async fn find(self, ctx: &Context) -> Result<Vec<SearchResult<T>>> {
+        let Self { query, limit, .. } = self;
+        let target = SearchTarget::of::<T>();
+        let hits = target.search(ctx, &query, limit).await?;
+        hits.into_iter()
+            .map(|hit| {
+                let SearchHit {
+                    score,
+                    highlights,
+                    doc,
+                    ..
+                } = hit;
+                let entity = T::from_document(doc)?;
+                Ok(SearchResult {
+                    entity,
+                    score,
+                    highlights,
+                })
+            })
+            .collect()
+    }
+}
+
+/// A search across several entity types, whose hits are ranked together.
+#[derive(Debug, Clone)]
+pub struct GlobalSearchQuery {
+    query: String,
+    targets: Vec<SearchTarget>,
+    skip: Option<u32>,
+    limit: Option<u32>,
+}
+
+/// A type-erased search hit, which can be converted into an entity of its
+/// `object_type`.
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub object_type: ObjectType,
+    pub score: i32,
+    pub highlights: Vec<SearchHighlight>,
+    doc: Document,
+}
+
+impl SearchHit {
+    pub fn into_entity<T: Entity>(self) -> Result<T> {
+        if self.object_type != T::OBJECT_TYPE {
+            let message = format!(
+                "search hit is a {}, not a {}",
+                self.object_type,
+                T::OBJECT_TYPE
+            );
+            return Err(Error::internal(message));
+        }
+        T::from_document(self.doc)
+    }
+
+    fn object_id(&self) -> Option<&ObjectId> {
+        self.doc.get_object_id("_id").ok()
+    }
+}
+
+impl GlobalSearchQuery {
+    pub fn new(query: impl Into<String>) -> Self {
+        Self {
+            query: query.into(),
+            targets: Vec::new(),
+            skip: None,
+            limit: None,
+        }
+    }
+
+    /// Includes entities of type `T` in the search. Entities without any
+    /// search fields are ignored.
+    pub fn include<T: Entity>(mut self) -> Self {
+        if !T::SEARCH_FIELDS.is_empty() {
+            self.targets.push(SearchTarget::of::<T>());
+        }
+        self
+    }
+
+    pub fn skip(mut self, n: impl Into<Option<u32>>) -> Self {
+        self.skip = n.into();
+        self
+    }
+
+    pub fn take(mut self, n: impl Into<Option<u32>>) -> Self {
+        self.limit = n.into();
+        self
+    }
+
+    pub async fn find(self, ctx: &Context) -> Result<Vec<SearchHit>> {
+        let Self {
+            query,
+            targets,
+            skip,
+            limit,
+        } = self;
+        let skip = skip.unwrap_or_default();
+
+        // Each target needs to return enough hits to fill the requested
+        // page on its own.
+        let target_limit = limit.map(|limit| skip + limit);
+        let searches = targets
+            .iter()
+            .map(|target| target.search(ctx, &query, target_limit));
+        let mut hits = Vec::new();
+        for result in join_all(searches).await {
+            hits.extend(result?);
+        }
+
+        hits.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| a.object_id().cmp(&b.object_id()))
+        });
+        let hits = hits.into_iter().skip(skip as usize);
+        let hits = match limit {
+            Some(limit) => hits.take(limit as usize).collect(),
+            None => hits.collect(),
+        };
+        Ok(hits)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct SearchTarget {
+    object_type: ObjectType,
+    collection: &'static str,
+    fields: &'static [&'static str],
+}
+
+impl SearchTarget {
+    fn of<T: Entity>() -> Self {
+        Self {
+            object_type: T::OBJECT_TYPE,
+            collection: T::COLLECTION_NAME,
+            fields: T::SEARCH_FIELDS,
+        }
+    }
+
+    async fn search(
+        &self,
+        ctx: &Context,
+        query: &str,
+        limit: Option<u32>,
+    ) -> Result<Vec<SearchHit>> {
+        let (prefixes, grams) = query_tokens(query);
+        if prefixes.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let tokens: Vec<&String> = prefixes.iter().chain(&grams).collect();
+        let tokens_field = format!("${}", SEARCH_TOKENS_FIELD);
+        let mut pipeline = vec![
+            doc! { "$match": { SEARCH_TOKENS_FIELD: { "$in": tokens } } },
+            doc! {
+                "$addFields": {
+                    "_score": {
+                        "$add": [
+                            {
+                                "$multiply": [
+                                    PREFIX_WEIGHT,
+                                    { "$size": { "$setIntersection": [&tokens_field, &prefixes] } },
+                                ],
+                            },
+                            {
+                                "$multiply": [
+                                    TRIGRAM_WEIGHT,
+                                    { "$size": { "$setIntersection": [&tokens_field, &grams] } },
+                                ],
+                            },
+                        ],
+                    },
+                },
+            },
+            doc! { "$sort": { "_score": -1, "_id": 1 } },
+        ];
+        if let Some(limit) = limit {
+            pipeline.push(doc! { "$limit": limit });
+        }
+
+        let collection: Collection<Document> =
+            ctx.database.collection(self.collection);
+        let mut cursor = collection.aggregate(pipeline, None).await?;
+        let mut hits = Vec::new();
+        while let Some(doc) = cursor.next().await {
+            let mut doc = doc?;
+            let score = doc.get_i32("_score").unwrap_or_default();
+            doc.remove("_score");
+            let highlights = highlight(&doc, self.fields, query);
+            hits.push(SearchHit {
+                object_type: self.object_type.clone(),
+                score,
+                highlights,
+                doc,
+            });
+        }
+        Ok(hits)
+    }
+}
+
+/// Finds the ranges of each search field that match the query's words,
+/// either as word prefixes or (failing that) as shared trigrams.
+fn highlight(
+    doc: &Document,
+    fields: &[&str],
+    query: &str,
+) -> Vec<SearchHighlight> {
+    let query_words: Vec<Vec<char>> =
+        words(query).map(|word| word.chars().collect()).collect();
+    let mut highlights = Vec::new();
+    for field in fields {
+        let value = match doc.get_str(field) {
+            Ok(value) => value,
+            Err(_) => continue,
+        };
+
+        // Lowercase the value, remembering which of its characters each
+        // lowercase character came from, since some characters lowercase to
+        // more than one (i.e. "İ").
+        let mut chars = Vec::new();
+        let mut origins = Vec::new();
+        for (i, c) in value.chars().enumerate() {
+            for lower in c.to_lowercase() {
+                chars.push(lower);
+                origins.push(i);
+            }
+        }
+        let mut ranges = Vec::new();
+
+        // Find the start and end of each word in the value.
+        let mut start = None;
+        for (i, c) in chars.iter().chain(&[' ']).enumerate() {
+            match (c.is_alphanumeric(), start) {
+                (true, None) => start = Some(i),
+                (false, Some(word_start)) => {
+                    let word = &chars[word_start..i];
+                    ranges.extend(match_word(word, &query_words).map(
+                        |(s, e)| {
+                            let start = origins[word_start + s];
+                            let end = origins[word_start + e - 1] + 1;
+                            (start, end)
+                        },
+                    ));
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+
+        if !ranges.is_empty() {
+            ranges.sort_unstable();
+            ranges.dedup();
+            highlights.push(SearchHighlight {
+                field: field.to_string(),
+                value: value.to_owned(),
+                ranges,
+            });
+        }
+    }
+    highlights
+}
+
+fn match_word(
+    word: &[char],
+    query_words: &[Vec<char>],
+) -> Option<(usize, usize)> {
+    // Prefer the longest query word that prefixes this word.
+    let prefix_len = query_words
+        .iter()
+        .filter(|query| word.starts_with(query))
+        .map(Vec::len)
+        .max();
+    if let Some(len) = prefix_len {
+        return Some((0, len));
+    }
+
+    // Otherwise, span the trigrams shared with any query word.
+    let mut span: Option<(usize, usize)> = None;
+    for query in query_words {
+        for gram in query.windows(3) {
+            let position = word.windows(3).position(|window| window == gram);
+            if let Some(i) = position {
+                span = Some(match span {
+                    Some((s, e)) => (s.min(i), e.max(i + 3)),
+                    None => (i, i + 3),
+                });
+            }
+        }
+    }
+    span
+}
@@ -0,0 +1,257 @@
+use super::prelude::*;
+
+use crate::secrets;
+
+/// A permission that an API key can be granted.
+#[derive(
+    Debug,
+    Display,
+    Clone,
+    Copy,
+    Hash,
+    PartialEq,
+    Eq,
+    EnumString,
+    Serialize,
+    Deserialize,
+)]
+pub enum Scope {
+    #[display(fmt = "users:read")]
+    #[strum(serialize = "users:read")]
+    #[serde(rename = "users:read")]
+    UsersRead,
+
+    #[display(fmt = "users:write")]
+    #[strum(serialize = "users:write")]
+    #[serde(rename = "users:write")]
+    UsersWrite,
+
+    #[display(fmt = "memberships:read")]
+    #[strum(serialize = "memberships:read")]
+    #[serde(rename = "memberships:read")]
+    MembershipsRead,
+
+    #[display(fmt = "memberships:write")]
+    #[strum(serialize = "memberships:write")]
+    #[serde(rename = "memberships:write")]
+    MembershipsWrite,
+}
+
+impl Scope {
+    /// Returns whether `user` holds this permission themselves, which they
+    /// need in order to grant it to an API key.
+    pub fn is_held_by(self, user: &User) -> bool {
+        use Scope::*;
+        if user.is_admin {
+            return true;
+        }
+        match self {
+            UsersRead | MembershipsRead => user.account_type.is_member(),
+            UsersWrite | MembershipsWrite => false,
+        }
+    }
+}
+
+/// A non-human client of the API, such as a script or another tool, which
+/// authenticates with API keys.
+#[entity_fields]
+#[derive(Debug, Clone, Serialize, Deserialize, Builder, Entity)]
+#[builder(field_defaults(setter(into)))]
+pub struct ServiceAccount {
+    pub name: String,
+    pub description: String,
+
+    /// The user who created the service account.
+    pub creator: ObjectRef,
+}
+
+impl ServiceAccount {
+    pub fn creator(&self) -> FindOneQuery<User> {
+        User::find(&self.creator.id)
+    }
+
+    pub fn api_keys(&self) -> FindQuery<ApiKey> {
+        let account_ref = self.object_ref();
+        let conditions =
+            ApiKeyConditions::builder().account(account_ref).build();
+        ApiKey::filter(conditions)
+    }
+}
+
+#[async_trait]
+impl Entity for ServiceAccount {
+    const COLLECTION_NAME: &'static str = "service_accounts";
+
+    async fn before_save(&mut self, _: &Context) -> Result<()> {
+        self.name = self.name.trim().to_owned();
+        if self.name.is_empty() {
+            let error =
+                Error::validation("service account name must not be empty");
+            return Err(error);
+        }
+        Ok(())
+    }
+
+    async fn before_delete(&mut self, ctx: &Context) -> Result<()> {
+        // Delete the account's API keys along with it.
+        let account_ref = self.object_ref();
+        let conditions =
+            ApiKeyConditions::builder().account(account_ref).build();
+        ApiKey::delete_many(conditions, ctx).await?;
+        Ok(())
+    }
+}
+
+/// A secret key that a service account authenticates with.
+///
+/// Only a hash of the key is stored; the key itself is shown once, when it
+/// is created.
+#[entity_fields]
+#[derive(Debug, Clone, Serialize, Deserialize, Builder, Entity)]
+#[builder(field_defaults(setter(into)))]
+pub struct ApiKey {
+    pub account: ObjectRef,
+    pub name: String,
+
+    /// The first few characters of the key, to help recognize it.
+    pub prefix: String,
+
+    pub secret_hash: String,
+    pub scopes: Vec<Scope>,
+
+    #[builder(default)]
+    pub expires_at: Option<DateTime>,
+
+    #[builder(default, setter(skip))]
+    pub last_used_at: Option<DateTime>,
+
+    #[builder(default, setter(skip))]
+    pub revoked_at: Option<DateTime>,
+}
+
+impl ApiKey {
+    /// The prefix of every API key, which tells them apart from other
+    /// bearer tokens.
+    pub const KEY_PREFIX: &'static str = "lat_";
+
+    /// How often `last_used_at` is updated, in seconds.
+    pub const LAST_USED_RESOLUTION: i64 = 60;
+
+    const DISPLAY_PREFIX_LEN: usize = 12;
+
+    /// Generates a new key, returning it along with its display prefix and
+    /// hash.
+    pub fn generate() -> (String, String, String) {
+        let key = format!("{}{}", Self::KEY_PREFIX, secrets::generate());
+        let prefix = key[..Self::DISPLAY_PREFIX_LEN].to_owned();
+        let hash = secrets::hash(&key);
+        (key, prefix, hash)
+    }
+
+    pub fn is_key(token: &str) -> bool {
+        token.starts_with(Self::KEY_PREFIX)
+    }
+
+    pub fn find_by_key(key: &str) -> FindOneQuery<Self> {
+        let secret_hash = secrets::hash(key);
+        Self::find_by(doc! { "secret_hash": secret_hash })
+    }
+
+    pub fn account(&self) -> FindOneQuery<ServiceAccount> {
+        ServiceAccount::find(&self.account.id)
+    }
+
+    pub fn is_active(&self) -> bool {
+        if self.revoked_at.is_some() {
+            return false;
+        }
+        match self.expires_at {
+            Some(expires_at) => expires_at > Utc::now(),
+            None => true,
+        }
+    }
+
+    pub fn has_scope(&self, scope: Scope) -> bool {
+        self.scopes.contains(&scope)
+    }
+
+    pub fn revoke(&mut self) -> Result<()> {
+        if self.revoked_at.is_some() {
+            let error = Error::conflict("API key already revoked");
+            return Err(error);
+        }
+        self.revoked_at = Some(Utc::now());
+        Ok(())
+    }
+
+    /// Records that the key was just used, unless that was already recorded
+    /// recently.
+    ///
+    /// Only `last_used_at` is written (and only while the key is unrevoked),
+    /// so that a concurrent revocation or deletion isn't undone.
+    pub async fn touch(&mut self, ctx: &Context) -> Result<()> {
+        let now = Utc::now();
+        if let Some(last_used_at) = self.last_used_at {
+            let resolution = Duration::seconds(Self::LAST_USED_RESOLUTION);
+            if now - last_used_at < resolution {
+                return Ok(());
+            }
+        }
+
+        let conditions = doc! { "_id": &self.id, "revoked_at": Bson::Null };
+        let update = doc! { "$set": { "last_used_at": to_bson(&now)? } };
+        Self::collection(ctx)
+            .update_one(conditions, update, None)
+            .await?;
+        if let Some(cache) = ctx.cache() {
+            cache.invalidate::<Self>(&self.id);
+        }
+        self.last_used_at = Some(now);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Entity for ApiKey {
+    const COLLECTION_NAME: &'static str = "api_keys";
+
+    async fn before_save(&mut self, ctx: &Context) -> Result<()> {
+        self.name = self.name.trim().to_owned();
+        if self.name.is_empty() {
+            let error = Error::validation("API key name must not be empty");
+            return Err(error);
+        }
+        if self.scopes.is_empty() {
+            let error =
+                Error::validation("API key must have at least one scope");
+            return Err(error);
+        }
+        self.scopes.sort_by_key(ToString::to_string);
+        self.scopes.dedup();
+
+        // Check that the key's service account exists.
+        if !self.account().exists(ctx).await? {
+            return Err(Error::not_found("service account"));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Builder)]
+#[builder(field_defaults(default, setter(into)))]
+pub struct ApiKeyConditions {
+    pub account: Option<ObjectRef>,
+}
+
+impl From<ApiKeyConditions> for Document {
+    fn from(conditions: ApiKeyConditions) -> Document {
+        let mut doc = Document::new();
+
+        let ApiKeyConditions { account } = conditions;
+        if let Some(account_ref) = account {
+            doc.insert("account.id", account_ref.id);
+        }
+
+        doc
+    }
+}
@@ -0,0 +1,142 @@
+use super::prelude::*;
+
+#[derive(
+    Debug,
+    Display,
+    Clone,
+    Copy,
+    Hash,
+    PartialEq,
+    Eq,
+    EnumString,
+    Serialize,
+    Deserialize,
+)]
+pub enum SkillCategory {
+    Language,
+    Framework,
+    Platform,
+    Design,
+    Product,
+    Other,
+}
+
+#[entity_fields]
+#[derive(Debug, Clone, Serialize, Deserialize, Builder, Entity)]
+#[builder(field_defaults(setter(into)))]
+pub struct Skill {
+    #[entity(search)]
+    pub name: String,
+
+    pub category: SkillCategory,
+
+    /// Lowercase names that the skill can be looked up by, including its own
+    /// name (i.e. "react", "reactjs").
+    #[builder(default)]
+    pub aliases: Vec<String>,
+}
+
+impl Skill {
+    /// Finds skills by their names or aliases, ignoring case.
+    pub fn named(names: Vec<String>) -> FindQuery<Self> {
+        let conditions = SkillConditions::builder().names(names).build();
+        Self::filter(conditions)
+    }
+}
+
+#[async_trait]
+impl Entity for Skill {
+    const COLLECTION_NAME: &'static str = "skills";
+
+    async fn before_save(&mut self, ctx: &Context) -> Result<()> {
+        self.name = self.name.trim().to_owned();
+        if self.name.is_empty() {
+            let error = Error::validation("skill name must not be empty");
+            return Err(error);
+        }
+
+        // Normalize aliases, and make sure the skill's own name is one of
+        // them.
+        let mut aliases: Vec<String> = self
+            .aliases
+            .iter()
+            .map(|alias| alias.trim().to_lowercase())
+            .filter(|alias| !alias.is_empty())
+            .collect();
+        aliases.push(self.name.to_lowercase());
+        aliases.sort();
+        aliases.dedup();
+        self.aliases = aliases;
+
+        // Check that no other skill goes by the same name.
+        let mut conditions = doc! { "aliases": { "$in": &self.aliases } };
+        conditions.insert("_id", doc! { "$ne": &self.id });
+        if Skill::find_by(conditions).exists(ctx).await? {
+            let error =
+                Error::conflict("another skill has the same name or alias");
+            return Err(error);
+        }
+        Ok(())
+    }
+
+    async fn before_delete(&mut self, ctx: &Context) -> Result<()> {
+        // Check that no user has this skill.
+        let skill_ref = self.object_ref();
+        let conditions =
+            UserConditions::builder().skills(vec![skill_ref]).build();
+        if User::filter(conditions).count(ctx).await?.is_positive() {
+            let error = Error::conflict("skill currently in use");
+            return Err(error);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Builder)]
+#[builder(field_defaults(default, setter(into)))]
+pub struct SkillConditions {
+    pub names: Option<Vec<String>>,
+    pub category: Option<SkillCategory>,
+}
+
+impl From<SkillConditions> for Document {
+    fn from(conditions: SkillConditions) -> Document {
+        let mut doc = Document::new();
+
+        let SkillConditions { names, category } = conditions;
+        if let Some(names) = names {
+            let aliases: Vec<String> = names
+                .iter()
+                .map(|name| name.trim().to_lowercase())
+                .collect();
+            doc.insert("aliases", doc! { "$in": aliases });
+        }
+        if let Some(category) = category {
+            doc.insert("category", category.to_string());
+        }
+
+        doc
+    }
+}
+
+/// A user's proficiency with a skill.
+#[derive(Debug, Clone, Serialize, Deserialize, Builder)]
+#[builder(field_defaults(setter(into)))]
+pub struct UserSkill {
+    pub skill: ObjectRef,
+
+    /// A self-rating from 1 (beginner) to 5 (expert).
+    pub rating: u8,
+
+    #[builder(default)]
+    pub last_used: Option<Date>,
+}
+
+impl UserSkill {
+    pub const MIN_RATING: u8 = 1;
+    pub const MAX_RATING: u8 = 5;
+
+    pub fn skill(&self) -> FindOneQuery<Skill> {
+        Skill::find(&self.skill.id)
+    }
+}
@@ -0,0 +1,241 @@
+use super::prelude::*;
+
+use std::collections::HashSet;
+
+use futures::TryStreamExt;
+
+#[derive(
+    Debug,
+    Display,
+    Clone,
+    Copy,
+    Hash,
+    PartialEq,
+    Eq,
+    EnumString,
+    Serialize,
+    Deserialize,
+)]
+pub enum TeamKind {
+    Department,
+    Team,
+}
+
+/// A node in the organization's tree of departments and teams.
+#[entity_fields]
+#[derive(Debug, Clone, Serialize, Deserialize, Builder, Entity)]
+#[builder(field_defaults(setter(into)))]
+pub struct Team {
+    pub kind: TeamKind,
+
+    #[entity(search)]
+    pub name: String,
+
+    #[entity(search)]
+    pub description: String,
+
+    #[builder(default)]
+    pub parent: Option<ObjectRef>,
+}
+
+impl Team {
+    pub fn parent(&self) -> Option<FindOneQuery<Team>> {
+        self.parent
+            .as_ref()
+            .map(|parent_ref| Team::find(&parent_ref.id))
+    }
+
+    pub fn children(&self) -> FindQuery<Team> {
+        let parent_ref = self.object_ref();
+        let conditions = TeamConditions::builder().parent(parent_ref).build();
+        Team::filter(conditions)
+    }
+
+    pub fn roles(&self) -> FindQuery<MemberRole> {
+        let team_ref = self.object_ref();
+        let conditions = MemberRoleConditions::builder().team(team_ref).build();
+        MemberRole::filter(conditions)
+    }
+}
+
+#[async_trait]
+impl Entity for Team {
+    const COLLECTION_NAME: &'static str = "teams";
+
+    async fn before_save(&mut self, ctx: &Context) -> Result<()> {
+        // Check that the team's ancestors exist, and don't include the team
+        // itself.
+        let mut visited = HashSet::new();
+        let mut parent_ref = self.parent.clone();
+        while let Some(ObjectRef { id, .. }) = parent_ref {
+            if id == self.id {
+                let error =
+                    Error::validation("team must not be its own parent");
+                return Err(error);
+            }
+            if !visited.insert(id.clone()) {
+                let error = Error::validation("team's ancestors form a cycle");
+                return Err(error);
+            }
+            let parent = Team::find(&id)
+                .load(ctx)
+                .await?
+                .ok_or_else(|| Error::not_found("parent team"))?;
+            parent_ref = parent.parent;
+        }
+        Ok(())
+    }
+
+    async fn before_delete(&mut self, ctx: &Context) -> Result<()> {
+        // Check that no team or member role belongs to this team.
+        if self.children().count(ctx).await?.is_positive() {
+            let error = Error::conflict("team has child teams");
+            return Err(error);
+        }
+        if self.roles().count(ctx).await?.is_positive() {
+            let error = Error::conflict("team has member roles");
+            return Err(error);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Builder)]
+#[builder(field_defaults(default, setter(into)))]
+pub struct TeamConditions {
+    pub parent: Option<ObjectRef>,
+}
+
+impl From<TeamConditions> for Document {
+    fn from(conditions: TeamConditions) -> Document {
+        let mut doc = Document::new();
+
+        let TeamConditions { parent } = conditions;
+        if let Some(parent_ref) = parent {
+            doc.insert("parent.id", parent_ref.id);
+        }
+
+        doc
+    }
+}
+
+/// The organization's teams as of a date, with the people who held each
+/// role on that date.
+#[derive(Debug, Clone)]
+pub struct OrgChart {
+    pub teams: Vec<OrgChartTeam>,
+
+    /// Roles that don't belong to any team.
+    pub roles: Vec<OrgChartRole>,
+}
+
+#[derive(Debug, Clone)]
+pub struct OrgChartTeam {
+    pub team: Team,
+    pub roles: Vec<OrgChartRole>,
+    pub children: Vec<OrgChartTeam>,
+}
+
+#[derive(Debug, Clone)]
+pub struct OrgChartRole {
+    pub role: MemberRole,
+    pub members: Vec<User>,
+}
+
+impl OrgChart {
+    pub async fn build(ctx: &Context, date: Date) -> Result<Self> {
+        let teams: Vec<Team> =
+            Team::all().find(ctx).await?.try_collect().await?;
+        let roles: Vec<MemberRole> =
+            MemberRole::all().find(ctx).await?.try_collect().await?;
+
+        // Find the people who held each role on the date.
+        let memberships: Vec<Membership> = {
+            let conditions =
+                MembershipConditions::builder().active_at(date).build();
+            Membership::filter(conditions)
+                .find(ctx)
+                .await?
+                .try_collect()
+                .await?
+        };
+        let users: Map<ObjectId, User> = {
+            let mut ids: Vec<ObjectId> = memberships
+                .iter()
+                .map(|membership| membership.user.id.clone())
+                .collect();
+            ids.sort();
+            ids.dedup();
+            let conditions = UserConditions::builder().ids(ids).build();
+            let users: Vec<User> = User::filter(conditions)
+                .find(ctx)
+                .await?
+                .try_collect()
+                .await?;
+            users
+                .into_iter()
+                .map(|user| (user.id.clone(), user))
+                .collect()
+        };
+        let mut members: Map<ObjectId, Vec<User>> = Map::new();
+        for membership in memberships {
+            if let Some(user) = users.get(&membership.user.id) {
+                let role_members =
+                    members.entry(membership.role.id).or_default();
+                if !role_members.iter().any(|member| member.id == user.id) {
+                    role_members.push(user.to_owned());
+                }
+            }
+        }
+
+        // Group roles by their team.
+        let mut team_roles: Map<Option<ObjectId>, Vec<OrgChartRole>> =
+            Map::new();
+        for role in roles {
+            let team_id =
+                role.team.as_ref().map(|team_ref| team_ref.id.clone());
+            let mut members = members.remove(&role.id).unwrap_or_default();
+            members.sort_by(|a, b| {
+                let a = (&a.first_name, &a.last_name);
+                let b = (&b.first_name, &b.last_name);
+                a.cmp(&b)
+            });
+            let role = OrgChartRole { role, members };
+            team_roles.entry(team_id).or_default().push(role);
+        }
+
+        // Group teams by their parent, and assemble the tree from the root.
+        let mut children: Map<Option<ObjectId>, Vec<Team>> = Map::new();
+        for team in teams {
+            let parent_id =
+                team.parent.as_ref().map(|parent_ref| parent_ref.id.clone());
+            children.entry(parent_id).or_default().push(team);
+        }
+        let teams = Self::assemble(None, &mut children, &mut team_roles);
+        let roles = team_roles.remove(&None).unwrap_or_default();
+        let chart = Self { teams, roles };
+        Ok(chart)
+    }
+
+    fn assemble(
+        parent_id: Option<ObjectId>,
+        children: &mut Map<Option<ObjectId>, Vec<Team>>,
+        team_roles: &mut Map<Option<ObjectId>, Vec<OrgChartRole>>,
+    ) -> Vec<OrgChartTeam> {
+        let mut teams = children.remove(&parent_id).unwrap_or_default();
+        teams.sort_by(|a, b| a.name.cmp(&b.name));
+        teams
+            .into_iter()
+            .map(|team| {
+                let team_id = Some(team.id.clone());
+                let roles = team_roles.remove(&team_id).unwrap_or_default();
+                let children = Self::assemble(team_id, children, team_roles);
+                OrgChartTeam {
+                    team,
+                    roles,
+                    children,
+                }
+            })
+            .collect()
+    }
+}
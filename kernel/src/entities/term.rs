@@ -0,0 +1,202 @@
+use super::prelude::*;
+
+use chrono::Datelike;
+
+#[derive(
+    Debug,
+    Display,
+    Clone,
+    Copy,
+    Hash,
+    PartialEq,
+    Eq,
+    EnumString,
+    Serialize,
+    Deserialize,
+)]
+pub enum Season {
+    Winter,
+    Spring,
+    Fall,
+}
+
+impl Season {
+    /// Returns the season that a date nominally falls in: Winter is January
+    /// to April, Spring is May to August, and Fall is September to December.
+    pub fn of(date: Date) -> Self {
+        use Season::*;
+        match date.month() {
+            1..=4 => Winter,
+            5..=8 => Spring,
+            _ => Fall,
+        }
+    }
+}
+
+/// A term identified by its season and year, which may not have a
+/// corresponding `Term` yet.
+#[derive(
+    Debug, Display, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[display(fmt = "{} {}", season, year)]
+pub struct TermName {
+    pub season: Season,
+    pub year: i32,
+}
+
+impl TermName {
+    pub fn new(season: Season, year: i32) -> Self {
+        Self { season, year }
+    }
+
+    pub fn of(date: Date) -> Self {
+        Self::new(Season::of(date), date.year())
+    }
+}
+
+#[entity_fields]
+#[derive(Debug, Clone, Serialize, Deserialize, Builder, Entity)]
+#[builder(field_defaults(setter(into)))]
+pub struct Term {
+    pub season: Season,
+    pub year: i32,
+
+    #[entity(date)]
+    pub start: Date,
+
+    #[entity(date)]
+    pub end: Date,
+}
+
+impl Term {
+    /// Returns the name of the term, i.e. "Fall 2020".
+    pub fn name(&self) -> String {
+        TermName::new(self.season, self.year).to_string()
+    }
+
+    pub fn range(&self) -> DateRange {
+        DateRange::new(self.start, self.end)
+    }
+
+    pub fn find_by_season(season: Season, year: i32) -> FindOneQuery<Self> {
+        let conditions =
+            TermConditions::builder().season(season).year(year).build();
+        Self::find_by(conditions)
+    }
+
+    /// Finds the term whose official dates cover the given date.
+    pub fn find_at(date: Date) -> FindOneQuery<Self> {
+        let conditions = TermConditions::builder()
+            .overlapping(DateRange::new(date, date))
+            .build();
+        Self::find_by(conditions)
+    }
+
+    /// Finds the terms that start after the given date, soonest first.
+    pub fn after(date: Date) -> FindQuery<Self> {
+        let conditions = TermConditions::builder().starts_after(date).build();
+        Self::filter(conditions).sort(TermSorting::Start(SortingOrder::Asc))
+    }
+}
+
+#[async_trait]
+impl Entity for Term {
+    const COLLECTION_NAME: &'static str = "terms";
+
+    async fn before_save(&mut self, ctx: &Context) -> Result<()> {
+        // Check that the term doesn't end before it starts.
+        if self.end < self.start {
+            let error = Error::validation("term must not end before it starts");
+            return Err(error);
+        }
+
+        // Check that there is only one term per season and year.
+        let conditions = TermConditions::builder()
+            .season(self.season)
+            .year(self.year)
+            .build();
+        let mut conditions = Document::from(conditions);
+        conditions.insert("_id", doc! { "$ne": &self.id });
+        if Term::find_by(conditions).exists(ctx).await? {
+            let message = format!("term {} already exists", self.name());
+            return Err(Error::conflict(message));
+        }
+
+        // Check that the term doesn't overlap another, so that there's at
+        // most one term at any date.
+        let conditions =
+            TermConditions::builder().overlapping(self.range()).build();
+        let mut conditions = Document::from(conditions);
+        conditions.insert("_id", doc! { "$ne": &self.id });
+        if let Some(other) = Term::find_by(conditions).load(ctx).await? {
+            let message = format!("term overlaps {}", other.name());
+            return Err(Error::conflict(message));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Builder)]
+#[builder(field_defaults(default, setter(into)))]
+pub struct TermConditions {
+    pub season: Option<Season>,
+    pub year: Option<i32>,
+
+    /// Terms that share at least one day with the given range.
+    pub overlapping: Option<DateRange>,
+
+    /// Terms that start after the given date.
+    pub starts_after: Option<Date>,
+}
+
+impl From<TermConditions> for Document {
+    fn from(conditions: TermConditions) -> Document {
+        let mut doc = Document::new();
+
+        let TermConditions {
+            season,
+            year,
+            overlapping,
+            starts_after,
+        } = conditions;
+        if let Some(season) = season {
+            doc.insert("season", season.to_string());
+        }
+        if let Some(year) = year {
+            doc.insert("year", year);
+        }
+
+        let mut clauses: Vec<Document> = Vec::new();
+        if let Some(range) = overlapping {
+            let DateRange { start, end } = range;
+            clauses.push(doc! {
+                "start": { "$lte": date::to_date_time(end) },
+                "end": { "$gte": date::to_date_time(start) },
+            });
+        }
+        if let Some(date) = starts_after {
+            let date = date::to_date_time(date);
+            clauses.push(doc! { "start": { "$gt": date } });
+        }
+        if !clauses.is_empty() {
+            doc.insert("$and", clauses);
+        }
+
+        doc
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum TermSorting {
+    Start(SortingOrder),
+}
+
+impl From<TermSorting> for Document {
+    fn from(sorting: TermSorting) -> Document {
+        use TermSorting::*;
+        match sorting {
+            Start(order) => doc! { "start": order },
+        }
+    }
+}
@@ -1,21 +1,67 @@
 use super::prelude::*;
 
-#[derive(Debug, Clone, Serialize, Deserialize, Builder)]
-#[builder(field_defaults(setter(into)))]
-pub struct User {
-    #[builder(default, setter(skip))]
-    pub id: ObjectId,
+#[derive(
+    Debug,
+    Display,
+    Clone,
+    Copy,
+    Hash,
+    PartialEq,
+    Eq,
+    EnumString,
+    Serialize,
+    Deserialize,
+)]
+pub enum AccountType {
+    Member,
+    PartnerContact,
+    Alumni,
+}
 
-    #[builder(default = Utc::now(), setter(skip))]
-    pub created_at: DateTime,
+#[allow(clippy::derivable_impls)]
+impl Default for AccountType {
+    fn default() -> Self {
+        Self::Member
+    }
+}
 
-    #[builder(default = Utc::now(), setter(skip))]
-    pub updated_at: DateTime,
+impl AccountType {
+    pub fn is_member(self) -> bool {
+        self == AccountType::Member
+    }
+}
 
+#[entity_fields]
+#[derive(Debug, Clone, Serialize, Deserialize, Builder, Entity)]
+#[builder(field_defaults(setter(into)))]
+pub struct User {
+    #[entity(search)]
     pub first_name: String,
+
+    #[entity(search)]
     pub last_name: String,
+
+    /// The name the user goes by, if different from their first name.
+    #[builder(default)]
+    #[entity(search)]
+    pub preferred_name: Option<String>,
+
+    #[builder(default)]
+    pub pronouns: Option<String>,
+
+    #[entity(search)]
     pub email: String,
 
+    #[builder(default)]
+    #[serde(default)]
+    pub account_type: AccountType,
+
+    /// Whether the user may administer Lattice, i.e. by impersonating other
+    /// users.
+    #[builder(default)]
+    #[serde(default)]
+    pub is_admin: bool,
+
     #[builder(default)]
     pub phone: Option<String>,
 
@@ -26,28 +72,48 @@ pub struct User {
     pub website_url: Option<String>,
 
     #[builder(default)]
+    #[entity(search)]
     pub twitter_handle: Option<String>,
 
     #[builder(default)]
+    #[entity(search)]
     pub instagram_handle: Option<String>,
 
     #[builder(default)]
     pub bio: Option<String>,
-}
 
-impl Object for User {
-    const OBJECT_TYPE: ObjectType = ObjectType::User;
+    /// The user's program of study, i.e. "Computer Science".
+    #[builder(default)]
+    pub program: Option<String>,
 
-    fn object_id(&self) -> ObjectId {
-        self.id.clone()
-    }
-}
+    /// The user's year of study.
+    #[builder(default)]
+    pub year: Option<u8>,
 
-impl Entity for User {
-    const COLLECTION_NAME: &'static str = "users";
+    /// The term in which the user expects to graduate.
+    #[builder(default)]
+    pub graduation_term: Option<TermName>,
+
+    #[entity(date = "last_used")]
+    #[builder(default)]
+    #[serde(default)]
+    pub skills: Vec<UserSkill>,
 }
 
 impl User {
+    pub const MIN_YEAR: u8 = 1;
+    pub const MAX_YEAR: u8 = 6;
+
+    /// Returns the name to address the user by: their preferred name, or
+    /// otherwise their first name.
+    pub fn display_name(&self) -> &str {
+        self.preferred_name.as_deref().unwrap_or(&self.first_name)
+    }
+
+    pub fn full_name(&self) -> String {
+        format!("{} {}", self.display_name(), self.last_name)
+    }
+
     pub fn find_by_email(email: impl Into<String>) -> FindOneQuery<Self> {
         let email: String = email.into();
         Self::find_by(doc! { "email": email })
@@ -58,22 +124,103 @@ impl User {
         let conditions = MembershipConditions::builder().user(user_ref).build();
         Membership::filter(conditions)
     }
+
+    pub fn identity_links(&self) -> FindQuery<IdentityLink> {
+        let user_ref = self.object_ref();
+        let conditions =
+            IdentityLinkConditions::builder().user(user_ref).build();
+        IdentityLink::filter(conditions)
+    }
+}
+
+#[async_trait]
+impl Entity for User {
+    const COLLECTION_NAME: &'static str = "users";
+    const NATURAL_KEYS: &'static [&'static str] = &["email"];
+
+    async fn before_save(&mut self, ctx: &Context) -> Result<()> {
+        // Treat blank profile fields as missing.
+        let fields = vec![
+            &mut self.preferred_name,
+            &mut self.pronouns,
+            &mut self.program,
+        ];
+        for field in fields {
+            let value = field.take().map(|value| value.trim().to_owned());
+            *field = value.filter(|value| !value.is_empty());
+        }
+
+        // Check that the year of study is in range.
+        if let Some(year) = self.year {
+            if !(User::MIN_YEAR..=User::MAX_YEAR).contains(&year) {
+                let message = format!(
+                    "year of study must be between {} and {}",
+                    User::MIN_YEAR,
+                    User::MAX_YEAR
+                );
+                return Err(Error::validation(message));
+            }
+        }
+
+        // Check that skill ratings are in range, and that each skill is
+        // listed once.
+        let mut skill_ids: Vec<ObjectId> = Vec::new();
+        for skill in &self.skills {
+            let range = UserSkill::MIN_RATING..=UserSkill::MAX_RATING;
+            if !range.contains(&skill.rating) {
+                let message = format!(
+                    "skill rating must be between {} and {}",
+                    UserSkill::MIN_RATING,
+                    UserSkill::MAX_RATING
+                );
+                return Err(Error::validation(message));
+            }
+            if skill_ids.contains(&skill.skill.id) {
+                let error = Error::validation("skill listed more than once");
+                return Err(error);
+            }
+            skill_ids.push(skill.skill.id.clone());
+        }
+
+        // Check that the skills exist.
+        if !skill_ids.is_empty() {
+            let expected = skill_ids.len() as i64;
+            let conditions = doc! { "_id": { "$in": skill_ids } };
+            let count = Skill::filter(conditions).count(ctx).await?;
+            if count != expected {
+                return Err(Error::not_found("skill"));
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Builder)]
 #[builder(field_defaults(default, setter(into)))]
 pub struct UserConditions {
+    pub ids: Option<Vec<ObjectId>>,
     pub query: Option<String>,
+
+    /// Users that have all of the given skills.
+    pub skills: Option<Vec<ObjectRef>>,
 }
 
 impl From<UserConditions> for Document{
     fn from(conditions: UserConditions) -> Document {
-        let UserConditions{ query } = conditions;
+        let UserConditions{ ids, query, skills } = conditions;
 
         let mut doc = Document::new();
+        if let Some(ids) = ids {
+            doc.insert("_id", doc! { "$in": ids });
+        }
         if let Some(query) = query {
             doc.insert("$text", doc! { "$search": query });
         }
+        if let Some(skills) = skills {
+            let ids: Vec<ObjectId> =
+                skills.into_iter().map(|skill_ref| skill_ref.id).collect();
+            doc.insert("skills.skill.id", doc! { "$all": ids });
+        }
 
         doc
     }
@@ -0,0 +1,75 @@
+use crate::prelude::*;
+
+use bson::de::Error as BsonDeserializeError;
+use bson::ser::Error as BsonSerializeError;
+use mongodb::error::Error as DatabaseError;
+
+use std::error::Error as StdError;
+use std::result::Result as StdResult;
+
+pub type Result<T, E = Error> = StdResult<T, E>;
+
+#[derive(Debug, Display)]
+pub enum Error {
+    #[display(fmt = "{} not found", _0)]
+    NotFound(String),
+
+    #[display(fmt = "{}", _0)]
+    Conflict(String),
+
+    #[display(fmt = "{}", _0)]
+    Validation(String),
+
+    #[display(fmt = "database error: {}", _0)]
+    Database(Box<DatabaseError>),
+
+    #[display(fmt = "{}", _0)]
+    Internal(Box<dyn StdError + Send + Sync>),
+}
+
+impl Error {
+    pub fn not_found(what: impl Into<String>) -> Self {
+        Self::NotFound(what.into())
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::Conflict(message.into())
+    }
+
+    pub fn validation(message: impl Into<String>) -> Self {
+        Self::Validation(message.into())
+    }
+
+    pub fn internal(error: impl Into<Box<dyn StdError + Send + Sync>>) -> Self {
+        Self::Internal(error.into())
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        use Error::*;
+        match self {
+            Database(error) => error.source(),
+            Internal(error) => error.source(),
+            _ => None,
+        }
+    }
+}
+
+impl From<DatabaseError> for Error {
+    fn from(error: DatabaseError) -> Self {
+        Self::Database(Box::new(error))
+    }
+}
+
+impl From<BsonSerializeError> for Error {
+    fn from(error: BsonSerializeError) -> Self {
+        Self::internal(error)
+    }
+}
+
+impl From<BsonDeserializeError> for Error {
+    fn from(error: BsonDeserializeError) -> Self {
+        Self::internal(error)
+    }
+}
@@ -1,4 +1,11 @@
+extern crate self as lattice_kernel;
+
+mod error;
 mod prelude;
 
+pub mod blobs;
 pub mod entities;
 pub mod env;
+pub mod secrets;
+
+pub use error::{Error, Result};
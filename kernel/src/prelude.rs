@@ -23,9 +23,7 @@ pub use serde::{Deserialize, Deserializer, Serialize, Serializer};
 pub use futures::Stream;
 pub use futures::{FutureExt, StreamExt};
 
-pub use anyhow::Context as AnyhowContext;
-pub use anyhow::{bail, format_err};
-pub use anyhow::{Error, Result};
+pub use crate::error::{Error, Result};
 
 pub use tracing::{debug, debug_span};
 pub use tracing::{error, error_span};
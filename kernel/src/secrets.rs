@@ -0,0 +1,18 @@
+use rand::{thread_rng, RngCore};
+use sha2::{Digest, Sha256};
+
+/// Generates a random, hex-encoded secret, suitable for tokens and keys.
+pub fn generate() -> String {
+    let mut bytes = [0u8; 32];
+    thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// Hashes a secret for storage.
+///
+/// Secrets from `generate` have enough entropy that they don't need to be
+/// salted or stretched.
+pub fn hash(secret: &str) -> String {
+    let hash = Sha256::digest(secret.as_bytes());
+    hex::encode(hash)
+}
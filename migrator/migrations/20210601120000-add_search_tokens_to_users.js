@@ -0,0 +1,60 @@
+// Mirrors the tokenization in `kernel/src/entities/search.rs`.
+const MAX_PREFIX_LEN = 12;
+
+const words = (text) =>
+  text
+    .split(/[^\p{L}\p{N}]+/u)
+    .filter((word) => word.length > 0)
+    .map((word) => word.toLowerCase());
+
+const indexTokens = (values) => {
+  const tokens = new Set();
+  values.forEach((value) => {
+    words(value).forEach((word) => {
+      const chars = Array.from(word);
+      const maxLen = Math.min(chars.length, MAX_PREFIX_LEN);
+      for (let len = 1; len <= maxLen; len++) {
+        tokens.add(`p:${chars.slice(0, len).join("")}`);
+      }
+      for (let i = 0; i + 3 <= chars.length; i++) {
+        tokens.add(`t:${chars.slice(i, i + 3).join("")}`);
+      }
+    });
+  });
+  return Array.from(tokens).sort();
+};
+
+const SEARCH_FIELDS = [
+  "first_name",
+  "last_name",
+  "email",
+  "twitter_handle",
+  "instagram_handle",
+];
+
+module.exports = {
+  async up(db, client) {
+    const users = db.collection("users");
+    const cursor = users.find({});
+    while (await cursor.hasNext()) {
+      const user = await cursor.next();
+      const values = SEARCH_FIELDS.map((field) => user[field]).filter(
+        (value) => typeof value === "string"
+      );
+      await users.updateOne(
+        { _id: user._id },
+        { $set: { search_tokens: indexTokens(values) } }
+      );
+    }
+    return users.createIndex(
+      { search_tokens: 1 },
+      { name: "search_tokens", background: true }
+    );
+  },
+
+  async down(db, client) {
+    const users = db.collection("users");
+    await users.dropIndex("search_tokens");
+    return users.updateMany({}, { $unset: { search_tokens: "" } });
+  },
+};
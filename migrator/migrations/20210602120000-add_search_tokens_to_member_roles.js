@@ -0,0 +1,54 @@
+// Mirrors the tokenization in `kernel/src/entities/search.rs`.
+const MAX_PREFIX_LEN = 12;
+
+const words = (text) =>
+  text
+    .split(/[^\p{L}\p{N}]+/u)
+    .filter((word) => word.length > 0)
+    .map((word) => word.toLowerCase());
+
+const indexTokens = (values) => {
+  const tokens = new Set();
+  values.forEach((value) => {
+    words(value).forEach((word) => {
+      const chars = Array.from(word);
+      const maxLen = Math.min(chars.length, MAX_PREFIX_LEN);
+      for (let len = 1; len <= maxLen; len++) {
+        tokens.add(`p:${chars.slice(0, len).join("")}`);
+      }
+      for (let i = 0; i + 3 <= chars.length; i++) {
+        tokens.add(`t:${chars.slice(i, i + 3).join("")}`);
+      }
+    });
+  });
+  return Array.from(tokens).sort();
+};
+
+const SEARCH_FIELDS = ["name", "description"];
+
+module.exports = {
+  async up(db, client) {
+    const memberRoles = db.collection("member_roles");
+    const cursor = memberRoles.find({});
+    while (await cursor.hasNext()) {
+      const role = await cursor.next();
+      const values = SEARCH_FIELDS.map((field) => role[field]).filter(
+        (value) => typeof value === "string"
+      );
+      await memberRoles.updateOne(
+        { _id: role._id },
+        { $set: { search_tokens: indexTokens(values) } }
+      );
+    }
+    return memberRoles.createIndex(
+      { search_tokens: 1 },
+      { name: "search_tokens", background: true }
+    );
+  },
+
+  async down(db, client) {
+    const memberRoles = db.collection("member_roles");
+    await memberRoles.dropIndex("search_tokens");
+    return memberRoles.updateMany({}, { $unset: { search_tokens: "" } });
+  },
+};
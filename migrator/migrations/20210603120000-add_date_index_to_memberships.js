@@ -0,0 +1,14 @@
+module.exports = {
+  async up(db, client) {
+    const memberships = db.collection("memberships");
+    return memberships.createIndex(
+      { start: 1, end: 1 },
+      { name: "start_end", background: true }
+    );
+  },
+
+  async down(db, client) {
+    const memberships = db.collection("memberships");
+    return memberships.dropIndex("start_end");
+  },
+};
@@ -0,0 +1,16 @@
+module.exports = {
+  async up(db, client) {
+    const terms = db.collection("terms");
+    await terms.createIndex(
+      { year: 1, season: 1 },
+      { name: "year_season", unique: true, background: true }
+    );
+    await terms.createIndex({ start: 1, end: 1 }, { name: "start_end" });
+  },
+
+  async down(db, client) {
+    const terms = db.collection("terms");
+    await terms.dropIndex("year_season");
+    await terms.dropIndex("start_end");
+  },
+};
@@ -0,0 +1,24 @@
+module.exports = {
+  async up(db, client) {
+    const teams = db.collection("teams");
+    await teams.createIndex({ "parent.id": 1 }, { name: "parent_id" });
+    await teams.createIndex({ search_tokens: 1 }, { name: "search_tokens" });
+
+    const memberRoles = db.collection("member_roles");
+    await memberRoles.createIndex({ "team.id": 1 }, { name: "team_id" });
+    await memberRoles.createIndex(
+      { "reports_to.id": 1 },
+      { name: "reports_to_id" }
+    );
+  },
+
+  async down(db, client) {
+    const teams = db.collection("teams");
+    await teams.dropIndex("parent_id");
+    await teams.dropIndex("search_tokens");
+
+    const memberRoles = db.collection("member_roles");
+    await memberRoles.dropIndex("team_id");
+    await memberRoles.dropIndex("reports_to_id");
+  },
+};
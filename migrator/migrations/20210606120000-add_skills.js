@@ -0,0 +1,22 @@
+module.exports = {
+  async up(db, client) {
+    const skills = db.collection("skills");
+    await skills.createIndex({ aliases: 1 }, { name: "aliases" });
+    await skills.createIndex({ search_tokens: 1 }, { name: "search_tokens" });
+
+    const users = db.collection("users");
+    await users.createIndex(
+      { "skills.skill.id": 1 },
+      { name: "skills_skill_id" }
+    );
+  },
+
+  async down(db, client) {
+    const skills = db.collection("skills");
+    await skills.dropIndex("aliases");
+    await skills.dropIndex("search_tokens");
+
+    const users = db.collection("users");
+    await users.dropIndex("skills_skill_id");
+  },
+};
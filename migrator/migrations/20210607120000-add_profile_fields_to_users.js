@@ -0,0 +1,46 @@
+module.exports = {
+  async up(db, client) {
+    const users = db.collection("users");
+    await users.updateMany(
+      { preferred_name: { $exists: false } },
+      {
+        $set: {
+          preferred_name: null,
+          pronouns: null,
+          program: null,
+          year: null,
+          graduation_term: null,
+        },
+      }
+    );
+
+    // Include preferred names in text search.
+    await users.dropIndex("text");
+    await users.createIndex(
+      { first_name: "text", last_name: "text", preferred_name: "text" },
+      { name: "text" }
+    );
+  },
+
+  async down(db, client) {
+    const users = db.collection("users");
+    await users.dropIndex("text");
+    await users.createIndex(
+      { first_name: "text", last_name: "text" },
+      { name: "text" }
+    );
+
+    await users.updateMany(
+      {},
+      {
+        $unset: {
+          preferred_name: "",
+          pronouns: "",
+          program: "",
+          year: "",
+          graduation_term: "",
+        },
+      }
+    );
+  },
+};
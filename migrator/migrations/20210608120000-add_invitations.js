@@ -0,0 +1,16 @@
+module.exports = {
+  async up(db, client) {
+    const invitations = db.collection("invitations");
+    await invitations.createIndex(
+      { token_hash: 1 },
+      { name: "token_hash", unique: true }
+    );
+    await invitations.createIndex({ email: 1 }, { name: "email" });
+  },
+
+  async down(db, client) {
+    const invitations = db.collection("invitations");
+    await invitations.dropIndex("token_hash");
+    await invitations.dropIndex("email");
+  },
+};
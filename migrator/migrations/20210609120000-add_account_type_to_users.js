@@ -0,0 +1,14 @@
+module.exports = {
+  async up(db, client) {
+    const users = db.collection("users");
+    await users.updateMany(
+      { account_type: { $exists: false } },
+      { $set: { account_type: "Member" } }
+    );
+  },
+
+  async down(db, client) {
+    const users = db.collection("users");
+    await users.updateMany({}, { $unset: { account_type: "" } });
+  },
+};
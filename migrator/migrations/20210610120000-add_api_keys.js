@@ -0,0 +1,16 @@
+module.exports = {
+  async up(db, client) {
+    const keys = db.collection("api_keys");
+    await keys.createIndex(
+      { secret_hash: 1 },
+      { name: "secret_hash", unique: true }
+    );
+    await keys.createIndex({ "account.id": 1 }, { name: "account_id" });
+  },
+
+  async down(db, client) {
+    const keys = db.collection("api_keys");
+    await keys.dropIndex("secret_hash");
+    await keys.dropIndex("account_id");
+  },
+};
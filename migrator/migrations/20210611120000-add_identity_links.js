@@ -0,0 +1,19 @@
+module.exports = {
+  async up(db, client) {
+    const links = db.collection("identity_links");
+    await links.createIndex(
+      { provider: 1, subject: 1 },
+      { name: "provider_subject", unique: true }
+    );
+    await links.createIndex(
+      { "user.id": 1, provider: 1 },
+      { name: "user_id_provider", unique: true }
+    );
+  },
+
+  async down(db, client) {
+    const links = db.collection("identity_links");
+    await links.dropIndex("provider_subject");
+    await links.dropIndex("user_id_provider");
+  },
+};
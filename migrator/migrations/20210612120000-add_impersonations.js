@@ -0,0 +1,16 @@
+module.exports = {
+  async up(db, client) {
+    const impersonations = db.collection("impersonations");
+    await impersonations.createIndex(
+      { "admin.id": 1, "target.id": 1, updated_at: -1 },
+      { name: "admin_id_target_id_updated_at" }
+    );
+    await impersonations.createIndex({ "target.id": 1 }, { name: "target_id" });
+  },
+
+  async down(db, client) {
+    const impersonations = db.collection("impersonations");
+    await impersonations.dropIndex("admin_id_target_id_updated_at");
+    await impersonations.dropIndex("target_id");
+  },
+};
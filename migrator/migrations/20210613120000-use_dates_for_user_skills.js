@@ -0,0 +1,70 @@
+module.exports = {
+  async up(db, client) {
+    const users = db.collection("users");
+    await users.updateMany({ "skills.last_used": { $type: "string" } }, [
+      {
+        $set: {
+          skills: {
+            $map: {
+              input: "$skills",
+              as: "skill",
+              in: {
+                $mergeObjects: [
+                  "$$skill",
+                  {
+                    last_used: {
+                      $cond: [
+                        { $eq: [{ $type: "$$skill.last_used" }, "string"] },
+                        {
+                          $dateFromString: {
+                            dateString: "$$skill.last_used",
+                          },
+                        },
+                        "$$skill.last_used",
+                      ],
+                    },
+                  },
+                ],
+              },
+            },
+          },
+        },
+      },
+    ]);
+  },
+
+  async down(db, client) {
+    const users = db.collection("users");
+    await users.updateMany({ "skills.last_used": { $type: "date" } }, [
+      {
+        $set: {
+          skills: {
+            $map: {
+              input: "$skills",
+              as: "skill",
+              in: {
+                $mergeObjects: [
+                  "$$skill",
+                  {
+                    last_used: {
+                      $cond: [
+                        { $eq: [{ $type: "$$skill.last_used" }, "date"] },
+                        {
+                          $dateToString: {
+                            date: "$$skill.last_used",
+                            format: "%Y-%m-%d",
+                          },
+                        },
+                        "$$skill.last_used",
+                      ],
+                    },
+                  },
+                ],
+              },
+            },
+          },
+        },
+      },
+    ]);
+  },
+};
@@ -0,0 +1,31 @@
+module.exports = {
+  async up(db, client) {
+    const invitations = db.collection("invitations");
+    await invitations.updateMany({ expires_at: { $type: "string" } }, [
+      {
+        $set: {
+          expires_at: {
+            $dateFromString: {
+              dateString: "$expires_at",
+            },
+          },
+        },
+      },
+    ]);
+  },
+
+  async down(db, client) {
+    const invitations = db.collection("invitations");
+    await invitations.updateMany({ expires_at: { $type: "date" } }, [
+      {
+        $set: {
+          expires_at: {
+            $dateToString: {
+              date: "$expires_at",
+            },
+          },
+        },
+      },
+    ]);
+  },
+};